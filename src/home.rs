@@ -1,3 +1,5 @@
+use crate::pagination::Cursor;
+use crate::search::saved::PinnedSearch;
 use crate::search::SearchResponse;
 use crate::RequestImpl;
 use serde::{Deserialize, Serialize};
@@ -10,9 +12,37 @@ pub struct HomeResponse {
     pub favorites: Vec<SearchResponse>,
     pub reading: Vec<SearchResponse>,
     pub random: Vec<SearchResponse>,
+    #[serde(default)]
+    pub pinned: Vec<PinnedSearch>,
 }
 
 impl RequestImpl for HomeResponse {
     const ROUTE: &'static str = "home";
     const AUTH: bool = true;
 }
+
+#[derive(Serialize, Deserialize, Debug, Copy, Clone, Eq, PartialEq)]
+pub enum HomeSection {
+    Trending,
+    Newest,
+    LatestUpdates,
+    Favorites,
+    Reading,
+    Random,
+}
+
+/// loads more entries of one section of the [`HomeResponse`]
+///
+/// Response: [`crate::pagination::Page<SearchResponse>`]
+#[derive(Serialize, Deserialize, Debug)]
+pub struct HomeSectionRequest {
+    pub section: HomeSection,
+    pub limit: u32,
+    /// `None` for the first page
+    pub cursor: Option<Cursor>,
+}
+
+impl RequestImpl for HomeSectionRequest {
+    const ROUTE: &'static str = "home/section";
+    const AUTH: bool = true;
+}
@@ -1,8 +1,8 @@
 use crate::error::ApiErr;
 use crate::search::Status;
+use crate::title::TitleSet;
 use crate::{ApiErrorType, RequestImpl};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
 
 #[derive(Serialize, Deserialize)]
 pub struct MangaInfoRequest {
@@ -12,7 +12,7 @@ pub struct MangaInfoRequest {
 #[derive(Serialize, Deserialize)]
 pub struct MangaInfoResponse {
     pub manga_id: String,
-    pub titles: HashMap<String, Vec<String>>,
+    pub titles: TitleSet,
     pub kind: String,
     pub description: Option<String>,
     pub tags: Vec<Tag>,
@@ -58,6 +58,9 @@ pub struct Tag {
     pub tag: String,
     pub description: Option<String>,
     pub sex: u64,
+    /// reveals part of the story
+    #[serde(default)]
+    pub spoiler: bool,
 }
 
 #[derive(Serialize, Deserialize)]
@@ -4,10 +4,13 @@ pub mod fonts;
 pub mod home;
 pub mod image;
 pub mod info;
+pub mod metadata;
+pub mod pagination;
 pub mod reader;
 pub mod scrape;
 pub mod scraper;
 pub mod search;
+pub mod title;
 
 use crate::error::{ApiErr, ApiErrorType};
 use std::collections::HashMap;
@@ -61,6 +64,7 @@ impl Request {
     }
 }
 
+/// Response: [`Vec<scraper::SourceDescriptor>`]
 pub struct SearchUris;
 
 impl RequestImpl for SearchUris {
@@ -0,0 +1,389 @@
+use crate::error::{ApiErr, ApiErrorType};
+use crate::info::Tag;
+use crate::metadata::{
+    country_language, filter_name, push_title, staff_role, strip_html, unsupported, MangaMetadata,
+};
+use crate::scraper::{SimpleSearch, ValidSearch};
+use crate::search::Status;
+use crate::title::TitleSet;
+use serde::{Deserialize, Serialize};
+
+pub const ENDPOINT: &str = "https://graphql.anilist.co";
+
+/// fields requested for every manga
+pub const MEDIA_FIELDS: &str =
+    "id title { romaji english native } synonyms description(asHtml: false) \
+genres tags { name isMediaSpoiler isGeneralSpoiler } status countryOfOrigin format \
+startDate { year month day } coverImage { extraLarge large } \
+staff { edges { role node { name { full } } } } \
+relations { edges { relationType node { id type title { romaji } } } }";
+
+pub const SEARCH_QUERY: &str =
+    "query ($page: Int, $perPage: Int, $search: String, $sort: [MediaSort], \
+$status: MediaStatus, $format: MediaFormat, $startDate_like: String, $genre_in: [String], \
+$genre_not_in: [String]) { \
+Page(page: $page, perPage: $perPage) { pageInfo { hasNextPage } \
+media(type: MANGA, search: $search, sort: $sort, status: $status, format: $format, \
+startDate_like: $startDate_like, genre_in: $genre_in, genre_not_in: $genre_not_in) { ";
+
+pub const MEDIA_QUERY: &str = "query ($id: Int) { Media(id: $id, type: MANGA) { ";
+
+/// body of a POST to [`ENDPOINT`]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct GraphQlRequest<T> {
+    pub query: String,
+    pub variables: T,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchVariables {
+    pub page: u32,
+    pub per_page: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub search: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub sort: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub status: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub format: Option<String>,
+    #[serde(rename = "startDate_like", skip_serializing_if = "Option::is_none")]
+    pub start_date_like: Option<String>,
+    #[serde(rename = "genre_in", skip_serializing_if = "Vec::is_empty")]
+    pub genre_in: Vec<String>,
+    #[serde(rename = "genre_not_in", skip_serializing_if = "Vec::is_empty")]
+    pub genre_not_in: Vec<String>,
+}
+
+/// graphql search for `search`, which gets validated against [`ValidSearch::anilist`]
+///
+/// the tags of the search are the anilist genres
+pub fn search_request(
+    search: &SimpleSearch,
+    per_page: u32,
+) -> Result<GraphQlRequest<SearchVariables>, ApiErr> {
+    let vs = ValidSearch::anilist();
+    search.validate(&vs)?;
+    let genres = vs.all_tags();
+    // with the casing anilist expects
+    let genre = |tag: &String| {
+        genres
+            .iter()
+            .find(|v| v.eq_ignore_ascii_case(tag))
+            .cloned()
+            .unwrap_or_else(|| tag.clone())
+    };
+    let mut variables = SearchVariables {
+        page: search.page.max(1),
+        per_page,
+        search: Some(search.search.trim().to_string()).filter(|v| !v.is_empty()),
+        ..Default::default()
+    };
+    if let Some(sort) = &search.sort {
+        let sort = match sort.to_lowercase().as_str() {
+            "popularity" => "POPULARITY",
+            "score" => "SCORE",
+            "trending" => "TRENDING",
+            "created" => "ID",
+            "updated" => "UPDATED_AT",
+            _ => return Err(unsupported("Unsupported sort", sort)),
+        };
+        variables.sort.push(match search.desc {
+            true => format!("{sort}_DESC"),
+            false => sort.to_string(),
+        });
+    } else if variables.search.is_some() {
+        variables.sort.push("SEARCH_MATCH".to_string());
+    }
+    if let Some(status) = &search.status {
+        variables.status = Some(
+            match status.to_lowercase().as_str() {
+                "releasing" => "RELEASING",
+                "finished" => "FINISHED",
+                "hiatus" => "HIATUS",
+                "cancelled" => "CANCELLED",
+                "upcoming" => "NOT_YET_RELEASED",
+                _ => return Err(unsupported("Unsupported status", status)),
+            }
+            .to_string(),
+        );
+    }
+    variables.genre_in = search.tags.iter().map(genre).collect();
+    variables.genre_not_in = search.exclude_tags.iter().map(genre).collect();
+    for (name, value) in &search.filters {
+        match filter_name(&vs, name) {
+            "year" => variables.start_date_like = Some(format!("{value}%")),
+            "format" => variables.format = Some(value.to_uppercase()),
+            _ => return Err(unsupported("Unsupported filter", name)),
+        }
+    }
+    Ok(GraphQlRequest {
+        query: format!("{SEARCH_QUERY}{MEDIA_FIELDS} }} }} }}"),
+        variables,
+    })
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct IdVariables {
+    pub id: u64,
+}
+
+/// graphql request for the manga with the anilist `id`, answered with a [`MediaData`]
+pub fn media_request(id: u64) -> GraphQlRequest<IdVariables> {
+    GraphQlRequest {
+        query: format!("{MEDIA_QUERY}{MEDIA_FIELDS} }} }}"),
+        variables: IdVariables { id },
+    }
+}
+
+#[derive(Deserialize, Debug)]
+pub struct GraphQlResponse<T> {
+    pub data: Option<T>,
+    #[serde(default)]
+    pub errors: Vec<GraphQlError>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct GraphQlError {
+    pub message: String,
+}
+
+impl<T> GraphQlResponse<T> {
+    pub fn into_data(self) -> Result<T, ApiErr> {
+        match (self.data, self.errors.is_empty()) {
+            (Some(data), true) => Ok(data),
+            (_, _) => Err(ApiErr {
+                message: Some("AniList request failed".to_string()),
+                cause: Some(
+                    self.errors
+                        .into_iter()
+                        .map(|v| v.message)
+                        .collect::<Vec<_>>()
+                        .join("\n"),
+                ),
+                err_type: ApiErrorType::InternalError,
+            }),
+        }
+    }
+}
+
+/// answer of [`media_request`]
+#[derive(Deserialize, Debug)]
+pub struct MediaData {
+    #[serde(rename = "Media")]
+    pub media: Option<Media>,
+}
+
+/// answer of [`search_request`]
+#[derive(Deserialize, Debug)]
+pub struct PageData {
+    #[serde(rename = "Page")]
+    pub page: Page,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct Page {
+    pub page_info: PageInfo,
+    pub media: Vec<Media>,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct PageInfo {
+    pub has_next_page: bool,
+}
+
+/// manga with the fields of [`MEDIA_FIELDS`]
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct Media {
+    pub id: u64,
+    pub title: MediaTitle,
+    #[serde(default)]
+    pub synonyms: Vec<String>,
+    pub description: Option<String>,
+    #[serde(default)]
+    pub genres: Vec<String>,
+    #[serde(default)]
+    pub tags: Vec<MediaTag>,
+    pub status: Option<String>,
+    pub country_of_origin: Option<String>,
+    pub format: Option<String>,
+    pub cover_image: Option<CoverImage>,
+    pub staff: Option<Connection<StaffEdge>>,
+    pub relations: Option<Connection<RelationEdge>>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct MediaTitle {
+    pub romaji: Option<String>,
+    pub english: Option<String>,
+    pub native: Option<String>,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct MediaTag {
+    pub name: String,
+    /// spoils the story of this manga
+    #[serde(default)]
+    pub is_media_spoiler: bool,
+    /// spoils every story it is used for
+    #[serde(default)]
+    pub is_general_spoiler: bool,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct CoverImage {
+    pub extra_large: Option<String>,
+    pub large: Option<String>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct Connection<T> {
+    #[serde(default = "Vec::new")]
+    pub edges: Vec<T>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct StaffEdge {
+    pub role: Option<String>,
+    pub node: Staff,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct Staff {
+    pub name: StaffName,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct StaffName {
+    pub full: Option<String>,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct RelationEdge {
+    pub relation_type: Option<String>,
+    pub node: RelatedMedia,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct RelatedMedia {
+    pub id: u64,
+    #[serde(rename = "type")]
+    pub kind: Option<String>,
+}
+
+impl Media {
+    pub fn url(&self) -> String {
+        format!("https://anilist.co/manga/{}", self.id)
+    }
+
+    /// native and romaji titles are marked as original language of the country of origin,
+    /// synonyms have no language and are stored as `und`
+    pub fn titles(&self) -> TitleSet {
+        let mut titles = TitleSet::default();
+        let language = self
+            .country_of_origin
+            .as_deref()
+            .map(country_language)
+            .unwrap_or_else(|| "ja".to_string());
+        if let Some(native) = &self.title.native {
+            push_title(&mut titles, format!("{language}*"), native);
+        }
+        if let Some(romaji) = &self.title.romaji {
+            push_title(&mut titles, format!("{language}-ro*"), romaji);
+        }
+        if let Some(english) = &self.title.english {
+            push_title(&mut titles, "en".to_string(), english);
+        }
+        for synonym in &self.synonyms {
+            push_title(&mut titles, "und".to_string(), synonym);
+        }
+        titles
+    }
+
+    pub fn metadata(&self) -> MangaMetadata {
+        let mut authors = vec![];
+        let mut artists = vec![];
+        for edge in self.staff.iter().flat_map(|v| &v.edges) {
+            let name = match &edge.node.name.full {
+                Some(name) => name,
+                None => continue,
+            };
+            let (author, artist) = staff_role(edge.role.as_deref().unwrap_or_default());
+            if author && !authors.contains(name) {
+                authors.push(name.clone());
+            }
+            if artist && !artists.contains(name) {
+                artists.push(name.clone());
+            }
+        }
+        let tag = |name: &str, spoiler| Tag {
+            tag: name.to_string(),
+            description: None,
+            sex: 0,
+            spoiler,
+        };
+        let kind = match (self.format.as_deref(), self.country_of_origin.as_deref()) {
+            (Some("NOVEL"), _) => Some("novel"),
+            (Some("ONE_SHOT"), _) => Some("one shot"),
+            (_, Some("KR")) => Some("manhwa"),
+            (_, Some("CN" | "TW")) => Some("manhua"),
+            (Some(_), _) => Some("manga"),
+            (None, _) => None,
+        };
+        MangaMetadata {
+            url: self.url(),
+            titles: self.titles(),
+            kind: kind.map(ToString::to_string),
+            description: self.description.as_deref().map(strip_html),
+            tags: self
+                .genres
+                .iter()
+                .map(|genre| tag(genre, false))
+                .chain(
+                    self.tags
+                        .iter()
+                        .map(|v| tag(&v.name, v.is_media_spoiler || v.is_general_spoiler)),
+                )
+                .collect(),
+            status: self.status.as_deref().and_then(|status| match status {
+                "FINISHED" => Some(Status::Completed),
+                "RELEASING" => Some(Status::Ongoing),
+                "NOT_YET_RELEASED" => Some(Status::Upcoming),
+                "CANCELLED" => Some(Status::Dropped),
+                "HIATUS" => Some(Status::Hiatus),
+                _ => None,
+            }),
+            authors,
+            artists,
+            relations: self
+                .relations
+                .iter()
+                .flat_map(|v| &v.edges)
+                .map(|edge| {
+                    let kind = edge.node.kind.as_deref().unwrap_or("MANGA").to_lowercase();
+                    (
+                        edge.relation_type
+                            .as_deref()
+                            .unwrap_or("OTHER")
+                            .to_lowercase(),
+                        format!("https://anilist.co/{kind}/{}", edge.node.id),
+                    )
+                })
+                .collect(),
+            covers: self
+                .cover_image
+                .iter()
+                .flat_map(|v| [&v.extra_large, &v.large])
+                .flatten()
+                .cloned()
+                .collect(),
+        }
+    }
+}
@@ -0,0 +1,391 @@
+use crate::error::ApiErr;
+use crate::info::Tag;
+use crate::metadata::{
+    country_language, filter_name, push_title, staff_role, strip_html, unsupported, MangaMetadata,
+};
+use crate::scraper::{SimpleSearch, ValidSearch};
+use crate::search::Status;
+use crate::title::TitleSet;
+use serde::Deserialize;
+use std::collections::HashMap;
+use url::form_urlencoded;
+
+pub const ENDPOINT: &str = "https://kitsu.app/api/edge/manga";
+
+/// relationships needed by [`Document::metadata`]
+pub const INCLUDE: &str = "categories,staff.person,mediaRelationships.destination";
+
+/// url of the manga with the kitsu `id`, answered with a [`Document<MangaResource>`]
+pub fn manga_url(id: &str) -> String {
+    let query = form_urlencoded::Serializer::new(String::new())
+        .append_pair("include", INCLUDE)
+        .finish();
+    format!("{ENDPOINT}/{id}?{query}")
+}
+
+/// JSON:API query parameters in the order they are sent
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct SearchParams {
+    pub params: Vec<(String, String)>,
+}
+
+impl SearchParams {
+    pub fn query_string(&self) -> String {
+        form_urlencoded::Serializer::new(String::new())
+            .extend_pairs(&self.params)
+            .finish()
+    }
+
+    pub fn url(&self) -> String {
+        format!("{ENDPOINT}?{}", self.query_string())
+    }
+}
+
+/// query for `search`, which gets validated against [`ValidSearch::kitsu`]
+///
+/// kitsu orders text searches by relevance, so the sort is only sent without text,
+/// it also can't exclude categories
+pub fn search_params(search: &SimpleSearch, limit: u32) -> Result<SearchParams, ApiErr> {
+    let vs = ValidSearch::kitsu();
+    search.validate(&vs)?;
+    if let Some(tag) = search.exclude_tags.first() {
+        return Err(unsupported("Kitsu can't exclude categories", tag));
+    }
+    let mut params = vec![];
+    let mut push = |key: &str, value: String| params.push((key.to_string(), value));
+    let text = search.search.trim();
+    if !text.is_empty() {
+        push("filter[text]", text.to_string());
+    }
+    if !search.tags.is_empty() {
+        let categories = search
+            .tags
+            .iter()
+            .map(|tag| tag.to_lowercase().replace(' ', "-"))
+            .collect::<Vec<_>>();
+        push("filter[categories]", categories.join(","));
+    }
+    let mut filters = search.filters.iter().collect::<Vec<_>>();
+    filters.sort();
+    for (name, value) in filters {
+        match filter_name(&vs, name) {
+            "subtype" => push("filter[subtype]", value.to_string()),
+            _ => return Err(unsupported("Unsupported filter", name)),
+        }
+    }
+    if let (Some(sort), true) = (&search.sort, text.is_empty()) {
+        // a lower rank is more popular
+        let (field, desc) = match sort.to_lowercase().as_str() {
+            "popularity" => ("popularityRank", !search.desc),
+            "rating" => ("averageRating", search.desc),
+            "updated" => ("updatedAt", search.desc),
+            "created" => ("createdAt", search.desc),
+            _ => return Err(unsupported("Unsupported sort", sort)),
+        };
+        push(
+            "sort",
+            match desc {
+                true => format!("-{field}"),
+                false => field.to_string(),
+            },
+        );
+    }
+    push("page[limit]", limit.to_string());
+    push(
+        "page[offset]",
+        (search.page.saturating_sub(1) as u64 * limit as u64).to_string(),
+    );
+    Ok(SearchParams { params })
+}
+
+/// JSON:API document with the resources it references in `included`
+#[derive(Deserialize, Debug)]
+pub struct Document<T> {
+    pub data: T,
+    #[serde(default)]
+    pub included: Vec<Included>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct MangaResource {
+    pub id: String,
+    pub attributes: MangaAttributes,
+    #[serde(default)]
+    pub relationships: HashMap<String, Relationship>,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct MangaAttributes {
+    /// keys like `en`, `ja_jp` or `en_jp` for the romanized japanese title
+    #[serde(default)]
+    pub titles: HashMap<String, Option<String>>,
+    pub canonical_title: Option<String>,
+    pub abbreviated_titles: Option<Vec<String>>,
+    pub synopsis: Option<String>,
+    pub description: Option<String>,
+    pub status: Option<String>,
+    pub subtype: Option<String>,
+    pub poster_image: Option<PosterImage>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct PosterImage {
+    pub original: Option<String>,
+    pub large: Option<String>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct Relationship {
+    /// only present if the relationship is included
+    pub data: Option<Linkage>,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(untagged)]
+pub enum Linkage {
+    One(ResourceRef),
+    Many(Vec<ResourceRef>),
+}
+
+impl Linkage {
+    fn refs(&self) -> &[ResourceRef] {
+        match self {
+            Linkage::One(one) => std::slice::from_ref(one),
+            Linkage::Many(many) => many,
+        }
+    }
+}
+
+#[derive(Deserialize, Debug, Clone, Eq, PartialEq)]
+pub struct ResourceRef {
+    #[serde(rename = "type")]
+    pub kind: String,
+    pub id: String,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(tag = "type")]
+pub enum Included {
+    #[serde(rename = "categories")]
+    Category {
+        id: String,
+        attributes: CategoryAttributes,
+    },
+    #[serde(rename = "mediaStaff")]
+    MediaStaff {
+        id: String,
+        attributes: RoleAttributes,
+        #[serde(default)]
+        relationships: HashMap<String, Relationship>,
+    },
+    #[serde(rename = "people")]
+    Person {
+        id: String,
+        attributes: PersonAttributes,
+    },
+    #[serde(rename = "mediaRelationships")]
+    MediaRelationship {
+        id: String,
+        attributes: RoleAttributes,
+        #[serde(default)]
+        relationships: HashMap<String, Relationship>,
+    },
+    #[serde(other)]
+    Other,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct CategoryAttributes {
+    pub title: String,
+    pub description: Option<String>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct RoleAttributes {
+    pub role: Option<String>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct PersonAttributes {
+    pub name: Option<String>,
+}
+
+impl Included {
+    fn is(&self, r: &ResourceRef) -> bool {
+        let (kind, id) = match self {
+            Included::Category { id, .. } => ("categories", id),
+            Included::MediaStaff { id, .. } => ("mediaStaff", id),
+            Included::Person { id, .. } => ("people", id),
+            Included::MediaRelationship { id, .. } => ("mediaRelationships", id),
+            Included::Other => return false,
+        };
+        kind == r.kind && *id == r.id
+    }
+}
+
+/// refs of the relationship `name`, empty if it wasn't included
+fn related<'a>(relationships: &'a HashMap<String, Relationship>, name: &str) -> &'a [ResourceRef] {
+    relationships
+        .get(name)
+        .and_then(|v| v.data.as_ref())
+        .map(Linkage::refs)
+        .unwrap_or_default()
+}
+
+/// language and whether it is romanized for title keys like `en_jp`
+fn title_language(key: &str) -> (String, bool) {
+    match key.split_once('_') {
+        Some(("en", country)) if !matches!(country, "us" | "gb") => {
+            (country_language(country), true)
+        }
+        Some((language, _)) => (language.to_string(), false),
+        None => (key.to_string(), false),
+    }
+}
+
+impl MangaResource {
+    pub fn url(&self) -> String {
+        format!("https://kitsu.app/manga/{}", self.id)
+    }
+
+    /// the language with a romanized title is marked as original,
+    /// abbreviations and a canonical title which isn't one of the titles are stored as `und`
+    pub fn titles(&self) -> TitleSet {
+        let mut keys = self
+            .attributes
+            .titles
+            .iter()
+            .filter_map(|(key, title)| Some((title_language(key), title.as_ref()?)))
+            .collect::<Vec<_>>();
+        keys.sort();
+        let original = keys
+            .iter()
+            .find(|((_, romanized), _)| *romanized)
+            .map(|((language, _), _)| language.clone());
+        let mut titles = TitleSet::default();
+        for ((language, romanized), title) in keys {
+            let mut key = language.clone();
+            if romanized {
+                key.push_str("-ro");
+            }
+            if original.as_ref() == Some(&language) {
+                key.push('*');
+            }
+            push_title(&mut titles, key, title);
+        }
+        let canonical = self.attributes.canonical_title.iter();
+        for title in canonical.chain(self.attributes.abbreviated_titles.iter().flatten()) {
+            if !titles.values().flatten().any(|v| v == title) {
+                push_title(&mut titles, "und".to_string(), title);
+            }
+        }
+        titles
+    }
+}
+
+impl Document<MangaResource> {
+    fn find(&self, r: &ResourceRef) -> Option<&Included> {
+        self.included.iter().find(|v| v.is(r))
+    }
+
+    pub fn metadata(&self) -> MangaMetadata {
+        let manga = &self.data;
+        let attributes = &manga.attributes;
+        let tags = related(&manga.relationships, "categories")
+            .iter()
+            .filter_map(|r| match self.find(r)? {
+                Included::Category { attributes, .. } => Some(Tag {
+                    tag: attributes.title.clone(),
+                    description: attributes.description.clone().filter(|v| !v.is_empty()),
+                    sex: 0,
+                    spoiler: false,
+                }),
+                _ => None,
+            })
+            .collect();
+        let mut authors = vec![];
+        let mut artists = vec![];
+        for r in related(&manga.relationships, "staff") {
+            let (role, relationships) = match self.find(r) {
+                Some(Included::MediaStaff {
+                    attributes,
+                    relationships,
+                    ..
+                }) => (
+                    attributes.role.as_deref().unwrap_or_default(),
+                    relationships,
+                ),
+                _ => continue,
+            };
+            let name =
+                related(relationships, "person")
+                    .iter()
+                    .find_map(|r| match self.find(r)? {
+                        Included::Person { attributes, .. } => attributes.name.clone(),
+                        _ => None,
+                    });
+            let name = match name {
+                Some(name) => name,
+                None => continue,
+            };
+            let (author, artist) = staff_role(role);
+            if author && !authors.contains(&name) {
+                authors.push(name.clone());
+            }
+            if artist && !artists.contains(&name) {
+                artists.push(name);
+            }
+        }
+        let relations = related(&manga.relationships, "mediaRelationships")
+            .iter()
+            .filter_map(|r| match self.find(r)? {
+                Included::MediaRelationship {
+                    attributes,
+                    relationships,
+                    ..
+                } => {
+                    let destination = related(relationships, "destination").first()?;
+                    Some((
+                        attributes
+                            .role
+                            .clone()
+                            .unwrap_or_else(|| "other".to_string()),
+                        format!("https://kitsu.app/{}/{}", destination.kind, destination.id),
+                    ))
+                }
+                _ => None,
+            })
+            .collect();
+        MangaMetadata {
+            url: manga.url(),
+            titles: manga.titles(),
+            kind: attributes.subtype.clone(),
+            description: attributes
+                .description
+                .as_ref()
+                .or(attributes.synopsis.as_ref())
+                .map(|v| strip_html(v)),
+            tags,
+            status: attributes
+                .status
+                .as_deref()
+                .and_then(|status| match status {
+                    "current" => Some(Status::Ongoing),
+                    "finished" => Some(Status::Completed),
+                    "tba" | "unreleased" | "upcoming" => Some(Status::Upcoming),
+                    _ => None,
+                }),
+            authors,
+            artists,
+            relations,
+            covers: attributes
+                .poster_image
+                .iter()
+                .flat_map(|v| [&v.original, &v.large])
+                .flatten()
+                .cloned()
+                .collect(),
+        }
+    }
+}
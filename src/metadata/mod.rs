@@ -0,0 +1,99 @@
+//! mapping between the crate types and the metadata services the server imports from
+
+use crate::error::{ApiErr, ApiErrorType};
+use crate::info::Tag;
+use crate::scraper::ValidSearch;
+use crate::search::parser::find_field;
+use crate::search::Status;
+use crate::title::TitleSet;
+
+pub mod anilist;
+pub mod kitsu;
+
+/// manga entry of a metadata service in the shape of [`crate::info::MangaInfoResponse`]
+pub struct MangaMetadata {
+    /// url of the entry on the service
+    pub url: String,
+    pub titles: TitleSet,
+    pub kind: Option<String>,
+    pub description: Option<String>,
+    pub tags: Vec<Tag>,
+    pub status: Option<Status>,
+    pub authors: Vec<String>,
+    pub artists: Vec<String>,
+    /// relation like `sequel` and the url of the related entry on the service
+    pub relations: Vec<(String, String)>,
+    /// largest first
+    pub covers: Vec<String>,
+}
+
+fn unsupported(message: &str, cause: impl ToString) -> ApiErr {
+    ApiErr {
+        message: Some(message.to_string()),
+        cause: Some(cause.to_string()),
+        err_type: ApiErrorType::InvalidInput,
+    }
+}
+
+/// canonical name of a filter, which can be given with any case or as abbreviation
+fn filter_name<'a>(vs: &'a ValidSearch, name: &'a str) -> &'a str {
+    find_field(&vs.fields, name).map_or(name, |field| field.name.as_str())
+}
+
+/// `(author, artist)` for staff roles like `Story & Art` or `Original Story`
+fn staff_role(role: &str) -> (bool, bool) {
+    let words = role
+        .split(|c: char| !c.is_alphabetic())
+        .map(str::to_lowercase)
+        .collect::<Vec<_>>();
+    let has = |word: &str| words.iter().any(|v| v == word);
+    (has("story") || has("original"), has("art"))
+}
+
+/// plain text of a description, `<br>` becomes a line break and other tags are removed
+fn strip_html(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    let mut rest = s;
+    while let Some(start) = rest.find('<') {
+        out.push_str(&rest[..start]);
+        let end = match rest[start..].find('>') {
+            Some(end) => start + end,
+            None => {
+                rest = &rest[start..];
+                break;
+            }
+        };
+        let tag = rest[start + 1..end].trim().to_lowercase();
+        if tag.starts_with("br") {
+            out.push('\n');
+        }
+        rest = &rest[end + 1..];
+    }
+    out.push_str(rest);
+    let mut text = out.trim().to_string();
+    while text.contains("\n\n\n") {
+        text = text.replace("\n\n\n", "\n\n");
+    }
+    text
+}
+
+/// language of a country code like `JP`
+fn country_language(country: &str) -> String {
+    match country.to_lowercase().as_str() {
+        "jp" => "ja".to_string(),
+        "kr" => "ko".to_string(),
+        "cn" | "tw" | "hk" => "zh".to_string(),
+        country => country.to_string(),
+    }
+}
+
+fn push_title(titles: &mut TitleSet, key: String, title: &str) {
+    let title = title.trim();
+    if title.is_empty() {
+        return;
+    }
+    let entry = titles.entry(key).or_default();
+    if !entry.iter().any(|v| v == title) {
+        entry.push(title.to_string());
+    }
+}
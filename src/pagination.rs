@@ -0,0 +1,121 @@
+use crate::error::{ApiErr, ApiErrorType};
+use serde::{Deserialize, Serialize};
+
+/// opaque position in a result list
+///
+/// contains the sort keys of the last returned entry and its id as tie-breaker,
+/// so entries added or removed between requests dont cause duplicates or gaps
+#[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq, Hash)]
+#[serde(transparent)]
+pub struct Cursor(String);
+
+/// value the results are sorted by
+#[derive(Debug, Clone, PartialEq)]
+pub enum SortKey {
+    None,
+    Int(i64),
+    Float(f64),
+    String(String),
+}
+
+impl Cursor {
+    /// `keys` in the order the results are sorted by, like the order and its `then` keys
+    pub fn new(keys: &[SortKey], id: &str) -> Self {
+        let mut raw = format!("{}:", keys.len());
+        for key in keys {
+            let key = match key {
+                SortKey::None => "n".to_string(),
+                SortKey::Int(v) => format!("i{v}"),
+                SortKey::Float(v) => format!("f{v}"),
+                SortKey::String(v) => format!("s{v}"),
+            };
+            raw.push_str(&format!("{}:{key}", key.len()));
+        }
+        raw.push_str(id);
+        Self(raw.bytes().map(|b| format!("{b:02x}")).collect())
+    }
+
+    pub fn decode(&self) -> Result<(Vec<SortKey>, String), ApiErr> {
+        let err = || ApiErr {
+            message: Some("Invalid cursor".to_string()),
+            cause: Some(self.0.clone()),
+            err_type: ApiErrorType::InvalidInput,
+        };
+        if self.0.len() % 2 != 0 {
+            return Err(err());
+        }
+        let bytes = (0..self.0.len())
+            .step_by(2)
+            .map(|i| {
+                u8::from_str_radix(self.0.get(i..i + 2).ok_or_else(err)?, 16).map_err(|_| err())
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let raw = String::from_utf8(bytes).map_err(|_| err())?;
+        let (count, mut rest) = raw.split_once(':').ok_or_else(err)?;
+        let count = count.parse::<usize>().map_err(|_| err())?;
+        let mut keys = vec![];
+        for _ in 0..count {
+            let (len, tail) = rest.split_once(':').ok_or_else(err)?;
+            let len = len.parse::<usize>().map_err(|_| err())?;
+            if !tail.is_char_boundary(len) {
+                return Err(err());
+            }
+            let (key, tail) = tail.split_at(len);
+            rest = tail;
+            let mut chars = key.chars();
+            let kind = chars.next();
+            let value = chars.as_str();
+            keys.push(match kind {
+                Some('n') => SortKey::None,
+                Some('i') => SortKey::Int(value.parse().map_err(|_| err())?),
+                Some('f') => SortKey::Float(value.parse().map_err(|_| err())?),
+                Some('s') => SortKey::String(value.to_string()),
+                _ => return Err(err()),
+            });
+        }
+        Ok((keys, rest.to_string()))
+    }
+}
+
+/// response envelope for cursor based requests
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Page<T> {
+    pub items: Vec<T>,
+    /// cursor to request the entries after the last item, `None` if there are no more
+    pub next_cursor: Option<Cursor>,
+    pub has_more: bool,
+    /// estimated number of matches of the whole query
+    pub total_estimate: Option<u64>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cursor(s: &str) -> Cursor {
+        Cursor(s.bytes().map(|b| format!("{b:02x}")).collect())
+    }
+
+    #[test]
+    fn round_trips_every_key() {
+        let keys = vec![
+            SortKey::Float(4.5),
+            SortKey::String("a:1".to_string()),
+            SortKey::None,
+            SortKey::Int(-3),
+        ];
+        let cursor = Cursor::new(&keys, "id:7");
+        assert_eq!(cursor.decode().unwrap(), (keys, "id:7".to_string()));
+        let cursor = Cursor::new(&[], "x");
+        assert_eq!(cursor.decode().unwrap(), (vec![], "x".to_string()));
+    }
+
+    #[test]
+    fn rejects_invalid_cursors() {
+        for raw in ["", "x", "2:1:n", "1:5:s", "1:2:x1", "1:2:ia", "1:2:s\u{e9}"] {
+            assert!(cursor(raw).decode().is_err(), "{raw}");
+        }
+        assert!(Cursor("abc".to_string()).decode().is_err());
+        assert!(Cursor("zz".to_string()).decode().is_err());
+    }
+}
@@ -1,3 +1,4 @@
+use crate::title::TitleSet;
 use crate::RequestImpl;
 use serde::{Deserialize, Serialize};
 use std::collections::{HashMap, HashSet};
@@ -6,7 +7,7 @@ use std::sync::Arc;
 #[derive(Serialize, Deserialize)]
 pub struct MangaReaderResponse {
     pub manga_id: String,
-    pub titles: HashMap<String, Vec<String>>,
+    pub titles: TitleSet,
     pub kind: String,
     pub description: Option<String>,
     pub chapters: Vec<ReaderChapter>,
@@ -1,9 +1,14 @@
 use crate::error::{ApiErr, ApiErrorType};
-use crate::search::{DisplaySearch, Field, Status};
+use crate::pagination::Cursor;
+use crate::search::parser::{closest_match, find_field, parse_query_with_text};
+use crate::search::schema::field;
+use crate::search::{Array, DisplaySearch, Field, ItemKind, ItemOrArray, ItemValue, Status};
+use crate::title::TitleSet;
 use crate::RequestImpl;
 use serde::{Deserialize, Serialize};
 use std::borrow::Cow;
 use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
 
 #[derive(Serialize, Deserialize)]
 pub struct ExternalSearchRequest {
@@ -68,6 +73,22 @@ impl RequestImpl for ExternalSearchRequest {
     const AUTH: bool = true;
 }
 
+/// [`ExternalSearchRequest`] which continues after a cursor instead of a page counter
+///
+/// the page of `data` is ignored, the server keeps track of it in the cursor
+#[derive(Serialize, Deserialize)]
+pub struct ExternalSearchCursorRequest {
+    pub data: ExternalSearchData,
+    pub uri: String,
+    /// `None` for the first page
+    pub cursor: Option<Cursor>,
+}
+
+impl RequestImpl for ExternalSearchCursorRequest {
+    const ROUTE: &'static str = "external/search/cursor";
+    const AUTH: bool = true;
+}
+
 #[derive(Serialize, Deserialize, Debug)]
 pub struct ScrapeSearchResult {
     pub title: String,
@@ -98,10 +119,10 @@ impl DisplaySearch for ScrapeSearchResult {
         Cow::Owned(Status::Ongoing)
     }
 
-    fn titles(&self) -> Cow<HashMap<String, Vec<String>>> {
+    fn titles(&self) -> Cow<TitleSet> {
         let mut hm = HashMap::new();
         hm.insert("eng".to_string(), vec![self.title.clone()]);
-        Cow::Owned(hm)
+        Cow::Owned(TitleSet(hm))
     }
 
     fn cover(&self) -> &str {
@@ -112,11 +133,33 @@ impl DisplaySearch for ScrapeSearchResult {
 #[derive(Serialize, Deserialize)]
 pub struct ValidSearch {
     pub sorts: Vec<String>,
+    /// tags without group, every tag is allowed if this and `tag_groups` are empty
     pub tags: Vec<String>,
     pub status: Vec<String>,
+    #[serde(default)]
+    pub tag_groups: Vec<TagGroup>,
+    /// filters only this source understands, end up in [`SimpleSearch::filters`]
+    #[serde(default)]
+    pub fields: Vec<Field>,
+}
+
+/// tags which belong together like genres or themes
+#[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq)]
+pub struct TagGroup {
+    pub name: String,
+    pub tags: Vec<String>,
 }
 
 impl ValidSearch {
+    /// tags and the tags of every group
+    pub fn all_tags(&self) -> Vec<String> {
+        self.tags
+            .iter()
+            .chain(self.tag_groups.iter().flat_map(|group| &group.tags))
+            .cloned()
+            .collect()
+    }
+
     pub fn anilist() -> Self {
         Self {
             sorts: vec![
@@ -134,6 +177,44 @@ impl ValidSearch {
                 "cancelled".to_string(),
                 "upcoming".to_string(),
             ],
+            tag_groups: vec![TagGroup {
+                name: "Genres".to_string(),
+                tags: [
+                    "Action",
+                    "Adventure",
+                    "Comedy",
+                    "Drama",
+                    "Ecchi",
+                    "Fantasy",
+                    "Hentai",
+                    "Horror",
+                    "Mahou Shoujo",
+                    "Mecha",
+                    "Music",
+                    "Mystery",
+                    "Psychological",
+                    "Romance",
+                    "Sci-Fi",
+                    "Slice of Life",
+                    "Sports",
+                    "Supernatural",
+                    "Thriller",
+                ]
+                .into_iter()
+                .map(ToString::to_string)
+                .collect(),
+            }],
+            fields: vec![
+                field("year", &["y"], ItemKind::Int, "Year of the first release"),
+                Field {
+                    values: vec![
+                        "manga".to_string(),
+                        "novel".to_string(),
+                        "one_shot".to_string(),
+                    ],
+                    ..field("format", &["f"], ItemKind::String, "Format of the release")
+                },
+            ],
         }
     }
 
@@ -147,6 +228,19 @@ impl ValidSearch {
             ],
             tags: vec![],
             status: vec![],
+            tag_groups: vec![],
+            fields: vec![Field {
+                values: vec![
+                    "manga".to_string(),
+                    "manhua".to_string(),
+                    "manhwa".to_string(),
+                    "novel".to_string(),
+                    "oneshot".to_string(),
+                    "doujin".to_string(),
+                    "oel".to_string(),
+                ],
+                ..field("subtype", &["st"], ItemKind::String, "Kind of the release")
+            }],
         }
     }
 }
@@ -157,29 +251,176 @@ pub struct SimpleSearch {
     pub sort: Option<String>,
     pub desc: bool,
     pub status: Option<String>,
+    /// tags the results must have
     pub tags: Vec<String>,
+    /// tags the results must not have
+    #[serde(default)]
+    pub exclude_tags: Vec<String>,
     pub page: u32,
+    /// values of [`ValidSearch::fields`] by field name
+    #[serde(default)]
+    pub filters: HashMap<String, String>,
+}
+
+/// problem found in a [`SimpleSearch`]
+#[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq)]
+pub enum SearchValidationError {
+    InvalidSort {
+        value: String,
+        suggestion: Option<String>,
+    },
+    InvalidStatus {
+        value: String,
+        suggestion: Option<String>,
+    },
+    UnknownTag {
+        value: String,
+        suggestion: Option<String>,
+    },
+    TagIncludedAndExcluded {
+        tag: String,
+    },
+    UnknownFilter {
+        name: String,
+        suggestion: Option<String>,
+    },
+    InvalidFilter {
+        name: String,
+        value: String,
+        suggestion: Option<String>,
+    },
+}
+
+impl Display for SearchValidationError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let suggestion = match self {
+            SearchValidationError::InvalidSort { value, suggestion } => {
+                write!(f, "Unknown sort \"{value}\"")?;
+                suggestion
+            }
+            SearchValidationError::InvalidStatus { value, suggestion } => {
+                write!(f, "Unknown status \"{value}\"")?;
+                suggestion
+            }
+            SearchValidationError::UnknownTag { value, suggestion } => {
+                write!(f, "Unknown tag \"{value}\"")?;
+                suggestion
+            }
+            SearchValidationError::TagIncludedAndExcluded { tag } => {
+                return write!(f, "Tag \"{tag}\" is included and excluded");
+            }
+            SearchValidationError::UnknownFilter { name, suggestion } => {
+                write!(f, "Unknown filter \"{name}\"")?;
+                suggestion
+            }
+            SearchValidationError::InvalidFilter {
+                name,
+                value,
+                suggestion,
+            } => {
+                write!(f, "\"{value}\" is not allowed for {name}")?;
+                suggestion
+            }
+        };
+        if let Some(suggestion) = suggestion {
+            write!(f, ", did you mean \"{suggestion}\"?")?;
+        }
+        Ok(())
+    }
+}
+
+impl From<Vec<SearchValidationError>> for ApiErr {
+    fn from(value: Vec<SearchValidationError>) -> Self {
+        ApiErr {
+            message: Some(
+                value
+                    .iter()
+                    .map(|v| v.to_string())
+                    .collect::<Vec<_>>()
+                    .join("\n"),
+            ),
+            cause: None,
+            err_type: ApiErrorType::InvalidInput,
+        }
+    }
 }
 
 impl SimpleSearch {
-    pub fn validate(&self, vs: &ValidSearch) -> bool {
-        if let Some(v) = &self.sort {
-            if !vs.sorts.contains(v) {
-                return false;
+    /// checks every value and returns all problems at once, values are compared case insensitive
+    ///
+    /// tags are only checked if the source has a tag catalog
+    pub fn validate(&self, vs: &ValidSearch) -> Result<(), Vec<SearchValidationError>> {
+        let mut errors = vec![];
+        let suggest = |value: &str, candidates: &[String]| {
+            closest_match(value, candidates.iter().map(String::as_str)).map(ToString::to_string)
+        };
+        let known =
+            |value: &str, values: &[String]| values.iter().any(|v| v.eq_ignore_ascii_case(value));
+        if let Some(value) = &self.sort {
+            if !known(value, &vs.sorts) {
+                errors.push(SearchValidationError::InvalidSort {
+                    value: value.clone(),
+                    suggestion: suggest(value, &vs.sorts),
+                });
+            }
+        }
+        if let Some(value) = &self.status {
+            if !known(value, &vs.status) {
+                errors.push(SearchValidationError::InvalidStatus {
+                    value: value.clone(),
+                    suggestion: suggest(value, &vs.status),
+                });
             }
         }
-        if let Some(v) = &self.status {
-            if !vs.status.contains(v) {
-                return false;
+        let catalog = vs.all_tags();
+        for tag in self.tags.iter().chain(&self.exclude_tags) {
+            if !catalog.is_empty() && !known(tag, &catalog) {
+                errors.push(SearchValidationError::UnknownTag {
+                    value: tag.clone(),
+                    suggestion: suggest(tag, &catalog),
+                });
             }
         }
         for tag in &self.tags {
-            if !vs.tags.contains(tag) {
-                //TODO:
-                //return false;
+            if self
+                .exclude_tags
+                .iter()
+                .any(|v| v.eq_ignore_ascii_case(tag))
+            {
+                errors.push(SearchValidationError::TagIncludedAndExcluded { tag: tag.clone() });
             }
         }
-        true
+        let mut filters = self.filters.iter().collect::<Vec<_>>();
+        filters.sort();
+        for (name, value) in filters {
+            let field = match find_field(&vs.fields, name) {
+                Some(field) => field,
+                None => {
+                    errors.push(SearchValidationError::UnknownFilter {
+                        name: name.clone(),
+                        suggestion: closest_match(name, vs.fields.iter().map(|v| v.name.as_str()))
+                            .map(ToString::to_string),
+                    });
+                    continue;
+                }
+            };
+            let valid = match field.values.is_empty() {
+                true => field.kind.parse(value).is_ok(),
+                false => known(value, &field.values),
+            };
+            if !valid {
+                errors.push(SearchValidationError::InvalidFilter {
+                    name: name.clone(),
+                    value: value.clone(),
+                    suggestion: suggest(value, &field.values),
+                });
+            }
+        }
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
     }
 }
 
@@ -190,7 +431,214 @@ pub enum ValidSearches {
 }
 
 impl ValidSearches {
+    /// fields of the query language the source understands,
+    /// `None` if the source only takes free text
     pub fn parser(&self) -> Option<Vec<Field>> {
-        None
+        let vs = match self {
+            ValidSearches::String => return None,
+            ValidSearches::ValidSearch(vs) => vs,
+        };
+        let mut fields = vec![
+            field("title", &["t"], ItemKind::String, "Text to search for"),
+            Field {
+                values: vs.sorts.clone(),
+                ..field("sort", &["order"], ItemKind::String, "Sort order")
+            },
+            field("desc", &[], ItemKind::Bool, "Sort descending"),
+        ];
+        if !vs.status.is_empty() {
+            fields.push(Field {
+                values: vs.status.clone(),
+                ..field("status", &["s"], ItemKind::String, "Publication status")
+            });
+        }
+        fields.push(Field {
+            values: vs.all_tags(),
+            ..field("tag", &["tags"], ItemKind::String, "Tag of the manga")
+        });
+        fields.extend(vs.fields.iter().cloned());
+        Some(fields)
+    }
+
+    /// parses `query` with the fields of [`ValidSearches::parser`]
+    ///
+    /// bare words and quoted strings are the text to search for,
+    /// only a list of items is supported, no `or` and only tags can be negated
+    pub fn search_data(&self, query: &str, page: u32) -> Result<ExternalSearchData, ApiErr> {
+        let fields = match self.parser() {
+            Some(fields) => fields,
+            None => return Ok(ExternalSearchData::String((query.to_string(), page))),
+        };
+        let unsupported = |cause: String| ApiErr {
+            message: Some("Query not supported by the source".to_string()),
+            cause: Some(cause),
+            err_type: ApiErrorType::InvalidInput,
+        };
+        let items = match parse_query_with_text(query, &fields, "title")? {
+            ItemOrArray::Array(Array { or: false, items }) => items,
+            item @ ItemOrArray::Item(_) => vec![item],
+            query => return Err(unsupported(query.to_string())),
+        };
+        let mut search = SimpleSearch {
+            search: String::new(),
+            sort: None,
+            desc: false,
+            status: None,
+            tags: vec![],
+            exclude_tags: vec![],
+            page,
+            filters: HashMap::new(),
+        };
+        let mut text = vec![];
+        for item in items {
+            let item = match item {
+                ItemOrArray::Item(item) => item,
+                item => return Err(unsupported(item.to_string())),
+            };
+            let cause = item.to_string();
+            let (field, value) = match (find_field(&fields, &item.data.name), item.data.value) {
+                (Some(field), ItemValue::None) if field.kind == ItemKind::Bool => {
+                    (field, ItemValue::Bool(true))
+                }
+                (Some(_), ItemValue::None) => return Err(unsupported(cause)),
+                (Some(field), value) => (field, value),
+                // items without value like `releasing` belong to the field which allows them
+                (None, ItemValue::None) => fields
+                    .iter()
+                    .find_map(|field| {
+                        let value = field
+                            .values
+                            .iter()
+                            .find(|v| v.eq_ignore_ascii_case(&item.data.name))?;
+                        Some((field, ItemValue::String(value.clone())))
+                    })
+                    .ok_or_else(|| unsupported(cause.clone()))?,
+                (None, _) => return Err(unsupported(cause)),
+            };
+            let name = field.name.as_str();
+            if item.not && name != "tag" {
+                return Err(unsupported(cause));
+            }
+            let values = match value {
+                ItemValue::Set(values) => values,
+                value => vec![value],
+            };
+            for value in values {
+                let value = match value {
+                    ItemValue::String(v) => v,
+                    value => value.to_string(),
+                };
+                match name {
+                    "title" => text.push(value),
+                    "sort" => search.sort = Some(value),
+                    "desc" => search.desc = value == "true",
+                    "status" => search.status = Some(value),
+                    "tag" if item.not => search.exclude_tags.push(value),
+                    "tag" => search.tags.push(value),
+                    name => {
+                        search.filters.insert(name.to_string(), value);
+                    }
+                }
+            }
+        }
+        search.search = text.join(" ");
+        Ok(ExternalSearchData::Simple(search))
+    }
+}
+
+/// everything a client needs to know about an external site
+#[derive(Serialize, Deserialize)]
+pub struct SourceDescriptor {
+    /// stable identifier used as `uri` in [`ExternalSearchRequest`]
+    pub id: String,
+    pub name: String,
+    pub icon_uri: String,
+    pub base_urls: Vec<String>,
+    /// language codes of the content
+    pub languages: Vec<String>,
+    pub nsfw: bool,
+    /// `None` if the site can't be searched
+    pub search: Option<ValidSearches>,
+    pub capabilities: SourceCapabilities,
+    pub login: LoginRequirement,
+    pub rate_limit: Option<RateLimit>,
+    /// changes when the scraper of the site changes
+    pub version: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Copy, Clone, Eq, PartialEq, Default)]
+pub struct SourceCapabilities {
+    pub latest: bool,
+    pub popular: bool,
+    pub chapters: bool,
+    pub pages: bool,
+}
+
+/// whether a [`crate::scrape::ScrapeAccount`] is needed
+#[derive(Serialize, Deserialize, Debug, Copy, Clone, Eq, PartialEq)]
+pub enum LoginRequirement {
+    None,
+    /// more content is available with an account
+    Optional,
+    Required,
+}
+
+/// requests the site allows before it starts rejecting them
+#[derive(Serialize, Deserialize, Debug, Copy, Clone, Eq, PartialEq)]
+pub struct RateLimit {
+    pub requests: u32,
+    pub per_secs: u32,
+}
+
+impl SourceDescriptor {
+    pub fn anilist() -> Self {
+        Self {
+            id: "anilist".to_string(),
+            name: "AniList".to_string(),
+            icon_uri: "https://anilist.co/favicon.ico".to_string(),
+            base_urls: vec![
+                "https://anilist.co".to_string(),
+                "https://graphql.anilist.co".to_string(),
+            ],
+            languages: vec!["en".to_string()],
+            nsfw: false,
+            search: Some(ValidSearches::ValidSearch(ValidSearch::anilist())),
+            capabilities: SourceCapabilities {
+                latest: true,
+                popular: true,
+                chapters: false,
+                pages: false,
+            },
+            login: LoginRequirement::Optional,
+            rate_limit: Some(RateLimit {
+                requests: 90,
+                per_secs: 60,
+            }),
+            version: "1".to_string(),
+        }
+    }
+
+    pub fn kitsu() -> Self {
+        Self {
+            id: "kitsu".to_string(),
+            name: "Kitsu".to_string(),
+            icon_uri: "https://kitsu.app/favicon.ico".to_string(),
+            base_urls: vec![
+                "https://kitsu.app".to_string(),
+                "https://kitsu.app/api/edge".to_string(),
+            ],
+            languages: vec!["en".to_string()],
+            nsfw: false,
+            search: Some(ValidSearches::ValidSearch(ValidSearch::kitsu())),
+            capabilities: SourceCapabilities {
+                latest: true,
+                popular: true,
+                chapters: false,
+                pages: false,
+            },
+            login: LoginRequirement::Optional,
+            rate_limit: None,
+            version: "1".to_string(),
+        }
     }
 }
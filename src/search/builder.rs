@@ -0,0 +1,289 @@
+use crate::search::date::DateQuery;
+use crate::search::schema::SearchSchema;
+use crate::search::validate::ValidationError;
+use crate::search::{Array, Item, ItemData, ItemOrArray, ItemValue, Status};
+use std::ops::{Range, RangeInclusive};
+
+/// fluent construction of a query tree like
+/// `Query::all().with("status", Status::Ongoing).without("tag", "gore").any(|q| ...)`
+#[derive(Debug, Clone)]
+pub struct Query {
+    or: bool,
+    items: Vec<ItemOrArray>,
+}
+
+impl Query {
+    /// matches if every item matches
+    pub fn all() -> Self {
+        Self {
+            or: false,
+            items: vec![],
+        }
+    }
+
+    /// matches if one of the items matches
+    pub fn any_of() -> Self {
+        Self {
+            or: true,
+            items: vec![],
+        }
+    }
+
+    pub fn with(self, name: &str, value: impl Into<ItemValue>) -> Self {
+        self.item(Item::new(data(name, value)))
+    }
+
+    pub fn without(self, name: &str, value: impl Into<ItemValue>) -> Self {
+        self.item(Item::new_exclude(data(name, value)))
+    }
+
+    pub fn item(mut self, item: Item) -> Self {
+        self.items.push(ItemOrArray::Item(item));
+        self
+    }
+
+    /// nested group which matches if one of its items matches
+    pub fn any(self, f: impl FnOnce(Query) -> Query) -> Self {
+        self.group(f(Query::any_of()))
+    }
+
+    /// nested group which matches if every item matches
+    pub fn every(self, f: impl FnOnce(Query) -> Query) -> Self {
+        self.group(f(Query::all()))
+    }
+
+    pub fn group(mut self, query: Query) -> Self {
+        self.items.push(query.build_unchecked());
+        self
+    }
+
+    /// checks names and value kinds against the schema
+    pub fn build(self, schema: &SearchSchema) -> Result<ItemOrArray, Vec<ValidationError>> {
+        let query = self.build_unchecked();
+        query.validate(schema)?;
+        Ok(query)
+    }
+
+    pub fn build_unchecked(self) -> ItemOrArray {
+        ItemOrArray::Array(Array {
+            or: self.or,
+            items: self.items,
+        })
+    }
+}
+
+fn data(name: &str, value: impl Into<ItemValue>) -> ItemData {
+    ItemData {
+        name: name.to_string(),
+        value: value.into(),
+    }
+}
+
+/// numbers which can be compared with [`ItemKind::CmpInt`](crate::search::ItemKind::CmpInt)
+/// or [`ItemKind::CmpFloat`](crate::search::ItemKind::CmpFloat) fields
+pub trait Comparable {
+    fn compare(self, eq: bool, bigger: bool) -> ItemValue;
+}
+
+impl Comparable for i64 {
+    fn compare(self, eq: bool, bigger: bool) -> ItemValue {
+        ItemValue::CmpInt {
+            eq,
+            bigger,
+            value: self,
+        }
+    }
+}
+
+impl Comparable for f32 {
+    fn compare(self, eq: bool, bigger: bool) -> ItemValue {
+        ItemValue::CmpFloat {
+            eq,
+            bigger,
+            value: self,
+        }
+    }
+}
+
+/// `>=value`
+pub fn at_least(value: impl Comparable) -> ItemValue {
+    value.compare(true, true)
+}
+
+/// `>value`
+pub fn more_than(value: impl Comparable) -> ItemValue {
+    value.compare(false, true)
+}
+
+/// `<=value`
+pub fn at_most(value: impl Comparable) -> ItemValue {
+    value.compare(true, false)
+}
+
+/// `<value`
+pub fn less_than(value: impl Comparable) -> ItemValue {
+    value.compare(false, false)
+}
+
+impl From<bool> for ItemValue {
+    fn from(value: bool) -> Self {
+        ItemValue::Bool(value)
+    }
+}
+
+impl From<i64> for ItemValue {
+    fn from(value: i64) -> Self {
+        ItemValue::Int(value)
+    }
+}
+
+impl From<f64> for ItemValue {
+    fn from(value: f64) -> Self {
+        ItemValue::Float(value)
+    }
+}
+
+impl From<&str> for ItemValue {
+    fn from(value: &str) -> Self {
+        ItemValue::String(value.to_string())
+    }
+}
+
+impl From<String> for ItemValue {
+    fn from(value: String) -> Self {
+        ItemValue::String(value)
+    }
+}
+
+impl From<Status> for ItemValue {
+    fn from(value: Status) -> Self {
+        ItemValue::String(value.to_string())
+    }
+}
+
+impl From<DateQuery> for ItemValue {
+    fn from(value: DateQuery) -> Self {
+        ItemValue::Date(value)
+    }
+}
+
+impl<T: Into<ItemValue>> From<Range<T>> for ItemValue {
+    fn from(value: Range<T>) -> Self {
+        ItemValue::Range {
+            min: Box::new(value.start.into()),
+            max: Box::new(value.end.into()),
+            inclusive: false,
+        }
+    }
+}
+
+impl<T: Into<ItemValue>> From<RangeInclusive<T>> for ItemValue {
+    fn from(value: RangeInclusive<T>) -> Self {
+        let (min, max) = value.into_inner();
+        ItemValue::Range {
+            min: Box::new(min.into()),
+            max: Box::new(max.into()),
+            inclusive: true,
+        }
+    }
+}
+
+/// set which matches if one of the values matches
+impl<T: Into<ItemValue>> From<Vec<T>> for ItemValue {
+    fn from(value: Vec<T>) -> Self {
+        ItemValue::Set(value.into_iter().map(Into::into).collect())
+    }
+}
+
+impl From<Query> for ItemOrArray {
+    fn from(value: Query) -> Self {
+        value.build_unchecked()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::search::ItemKind;
+
+    #[test]
+    fn builds_nested_queries() {
+        let query = Query::all()
+            .with("status", Status::Ongoing)
+            .without("tag", "gore")
+            .with("rating", at_least(4.0f32))
+            .any(|q| {
+                q.with("chapters", less_than(10i64))
+                    .with("chapters", 100i64..=200)
+                    .every(|q| {
+                        q.with("favorite", true)
+                            .with("kind", vec!["manhwa", "manhua"])
+                    })
+            })
+            .build(&SearchSchema::internal())
+            .unwrap();
+        assert_eq!(
+            query.to_string(),
+            "and:(status:\"ongoing\" tag:!\"gore\" rating:>=4 or:(chapters:<10 chapters:100..=200 \
+             and:(favorite:true kind:{\"manhwa\",\"manhua\"})))"
+        );
+    }
+
+    #[test]
+    fn rejects_kind_mismatch() {
+        let schema = SearchSchema::internal();
+        assert_eq!(
+            Query::all().with("favorite", 1i64).build(&schema),
+            Err(vec![ValidationError::KindMismatch {
+                path: vec![0],
+                field: "favorite".to_string(),
+                expected: ItemKind::Bool,
+                found: ItemKind::Int,
+            }])
+        );
+        assert_eq!(
+            Query::any_of()
+                .with("chapters", at_least(4.0f32))
+                .with("rating", 3i64..4)
+                .build(&schema),
+            Err(vec![
+                ValidationError::KindMismatch {
+                    path: vec![0],
+                    field: "chapters".to_string(),
+                    expected: ItemKind::CmpInt,
+                    found: ItemKind::CmpFloat,
+                },
+                ValidationError::KindMismatch {
+                    path: vec![1],
+                    field: "rating".to_string(),
+                    expected: ItemKind::Float,
+                    found: ItemKind::Int,
+                },
+            ])
+        );
+    }
+
+    #[test]
+    fn rejects_unknown_fields_and_values() {
+        let schema = SearchSchema::internal();
+        let errors = Query::all()
+            .every(|q| q.with("status", "finished").with("titel", "x"))
+            .build(&schema)
+            .unwrap_err();
+        assert_eq!(
+            errors.iter().map(ToString::to_string).collect::<Vec<_>>(),
+            vec![
+                "\"finished\" is not allowed for status, expected one of \
+                 dropped, hiatus, ongoing, completed, upcoming",
+                "Unknown field \"titel\", did you mean \"title\"?",
+            ]
+        );
+    }
+
+    #[test]
+    fn unchecked_keeps_everything() {
+        let query = Query::any_of().with("titel", 1.5).build_unchecked();
+        assert_eq!(query.to_string(), "or:(titel:1.5)");
+        assert_eq!(ItemOrArray::from(Query::all()).to_string(), "and:()");
+    }
+}
@@ -0,0 +1,590 @@
+use crate::error::{ApiErr, ApiErrorType};
+use crate::search::{Array, Item, ItemOrArray, ItemValue, Order, OrderBy, SearchRequest};
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
+
+/// query language the [`ItemOrArray`] gets compiled to
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Dialect {
+    /// placeholders are `$1`, `$2`, ...
+    Postgres,
+    /// placeholders are `$p1`, `$p2`, ...
+    SurrealQl,
+}
+
+impl Dialect {
+    fn placeholder(&self, index: usize) -> String {
+        match self {
+            Dialect::Postgres => format!("${index}"),
+            Dialect::SurrealQl => format!("$p{index}"),
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum ColumnKind {
+    /// compared with `=`, `<`, `>`, ...
+    Scalar,
+    /// strings are matched case insensitive with contains
+    Text,
+    /// array of strings, matches if one of them equals the value
+    Array,
+    /// boolean, an item without value like `favorite:` matches `true`
+    Bool,
+}
+
+#[derive(Debug, Clone)]
+pub struct Column {
+    pub name: String,
+    pub kind: ColumnKind,
+}
+
+/// maps the fields of the search to columns of the database
+///
+/// column names are inserted into the query as they are and must not come from user input
+#[derive(Debug, Clone, Default)]
+pub struct ColumnMapping {
+    pub fields: HashMap<String, Column>,
+    pub orders: HashMap<Order, String>,
+}
+
+impl ColumnMapping {
+    pub fn with_field(mut self, field: &str, column: &str, kind: ColumnKind) -> Self {
+        self.fields.insert(
+            field.to_string(),
+            Column {
+                name: column.to_string(),
+                kind,
+            },
+        );
+        self
+    }
+
+    pub fn with_order(mut self, order: Order, column: &str) -> Self {
+        self.orders.insert(order, column.to_string());
+        self
+    }
+}
+
+/// value bound to a placeholder
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub enum BindValue {
+    Bool(bool),
+    Int(i64),
+    Float(f64),
+    String(String),
+    Date(NaiveDate),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct CompiledQuery {
+    pub dialect: Dialect,
+    pub condition: String,
+    pub order_by: Option<String>,
+    pub limit: u32,
+    pub offset: u64,
+    /// the first value belongs to the placeholder with index 1
+    pub binds: Vec<BindValue>,
+}
+
+/// `WHERE .. ORDER BY .. LIMIT .. OFFSET ..`
+impl Display for CompiledQuery {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "WHERE {}", self.condition)?;
+        if let Some(order_by) = &self.order_by {
+            write!(f, " ORDER BY {order_by}")?;
+        }
+        let offset = match self.dialect {
+            Dialect::Postgres => "OFFSET",
+            Dialect::SurrealQl => "START",
+        };
+        write!(f, " LIMIT {} {offset} {}", self.limit, self.offset)
+    }
+}
+
+impl SearchRequest {
+    /// relative dates are resolved against `today`
+    pub fn compile(
+        &self,
+        mapping: &ColumnMapping,
+        dialect: Dialect,
+        today: NaiveDate,
+    ) -> Result<CompiledQuery, ApiErr> {
+        let (condition, binds) = compile_query(&self.query, mapping, dialect, today)?;
+        let keys = std::iter::once(OrderBy {
+            order: self.order,
+            desc: self.desc,
+        })
+        .chain(self.then.iter().copied())
+        .filter_map(|key| order_term(key, self.seed, mapping, dialect).transpose())
+        .collect::<Result<Vec<_>, _>>()?;
+        let order_by = match keys.is_empty() {
+            true => None,
+            false => Some(keys.join(", ")),
+        };
+        Ok(CompiledQuery {
+            dialect,
+            condition,
+            order_by,
+            limit: self.limit,
+            offset: self.page.saturating_sub(1) as u64 * self.limit as u64,
+            binds,
+        })
+    }
+}
+
+/// `None` if the order has no column
+///
+/// a column mapped to [`Order::Random`] is hashed with the seed to get a stable order,
+/// without one a seed can't be honored and is rejected
+fn order_term(
+    key: OrderBy,
+    seed: Option<u64>,
+    mapping: &ColumnMapping,
+    dialect: Dialect,
+) -> Result<Option<String>, ApiErr> {
+    let direction = match key.desc {
+        true => "DESC",
+        false => "ASC",
+    };
+    let column = mapping.orders.get(&key.order);
+    Ok(Some(match (key.order, column, seed, dialect) {
+        (Order::Random, Some(column), Some(seed), Dialect::Postgres) => {
+            format!("md5({column}::text || '{seed}') {direction}")
+        }
+        (Order::Random, Some(column), Some(seed), Dialect::SurrealQl) => {
+            format!("crypto::md5(string::concat({column}, '{seed}')) {direction}")
+        }
+        (Order::Random, None, Some(seed), _) => {
+            return Err(ApiErr {
+                message: Some("Seeded random order needs a column to hash".to_string()),
+                cause: Some(seed.to_string()),
+                err_type: ApiErrorType::InvalidInput,
+            })
+        }
+        (Order::Random, _, None, Dialect::Postgres) => "RANDOM()".to_string(),
+        (Order::Random, _, None, Dialect::SurrealQl) => "rand()".to_string(),
+        (_, Some(column), _, _) => format!("{column} {direction}"),
+        (_, None, _, _) => return Ok(None),
+    }))
+}
+
+/// compiles the query to a condition with placeholders and the values bound to them,
+/// relative dates are resolved against `today`
+pub fn compile_query(
+    query: &ItemOrArray,
+    mapping: &ColumnMapping,
+    dialect: Dialect,
+    today: NaiveDate,
+) -> Result<(String, Vec<BindValue>), ApiErr> {
+    let mut compiler = Compiler {
+        mapping,
+        dialect,
+        today,
+        binds: vec![],
+    };
+    let condition = compiler.compile(query)?;
+    Ok((condition, compiler.binds))
+}
+
+struct Compiler<'a> {
+    mapping: &'a ColumnMapping,
+    dialect: Dialect,
+    today: NaiveDate,
+    binds: Vec<BindValue>,
+}
+
+impl Compiler<'_> {
+    fn bind(&mut self, value: BindValue) -> String {
+        self.binds.push(value);
+        self.dialect.placeholder(self.binds.len())
+    }
+
+    fn equals(&mut self, col: &str, value: BindValue) -> String {
+        let p = self.bind(value);
+        format!("{col} = {p}")
+    }
+
+    fn compile(&mut self, query: &ItemOrArray) -> Result<String, ApiErr> {
+        match query {
+            ItemOrArray::Item(item) => self.compile_item(item),
+            ItemOrArray::Array(Array { or, items }) => {
+                if items.is_empty() {
+                    return Ok(match or {
+                        true => "false",
+                        false => "true",
+                    }
+                    .to_string());
+                }
+                let joiner = match or {
+                    true => " OR ",
+                    false => " AND ",
+                };
+                let items = items
+                    .iter()
+                    .map(|item| self.compile(item))
+                    .collect::<Result<Vec<_>, _>>()?;
+                Ok(format!("({})", items.join(joiner)))
+            }
+        }
+    }
+
+    fn compile_item(&mut self, item: &Item) -> Result<String, ApiErr> {
+        let name = &item.data.name;
+        let mapping = self.mapping;
+        let column = mapping.fields.get(name).ok_or_else(|| ApiErr {
+            message: Some(format!("Field {name} cannot be searched")),
+            cause: None,
+            err_type: ApiErrorType::InvalidInput,
+        })?;
+        let condition = self.condition(name, column, &item.data.value)?;
+        Ok(match item.not {
+            true => format!("NOT ({condition})"),
+            false => condition,
+        })
+    }
+
+    fn condition(
+        &mut self,
+        name: &str,
+        column: &Column,
+        value: &ItemValue,
+    ) -> Result<String, ApiErr> {
+        let col = column.name.as_str();
+        Ok(match (value, column.kind) {
+            (ItemValue::None, ColumnKind::Bool) => self.equals(col, BindValue::Bool(true)),
+            (ItemValue::None, _) => match self.dialect {
+                Dialect::Postgres => format!("{col} IS NOT NULL"),
+                Dialect::SurrealQl => format!("{col} != NONE"),
+            },
+            (ItemValue::String(s), ColumnKind::Text) => match self.dialect {
+                Dialect::Postgres => {
+                    let escaped = s
+                        .replace('\\', "\\\\")
+                        .replace('%', "\\%")
+                        .replace('_', "\\_");
+                    let p = self.bind(BindValue::String(format!("%{escaped}%")));
+                    format!("{col} ILIKE {p}")
+                }
+                Dialect::SurrealQl => {
+                    let p = self.bind(BindValue::String(s.to_lowercase()));
+                    format!("string::contains(string::lowercase({col}), {p})")
+                }
+            },
+            (ItemValue::String(s), ColumnKind::Array) => {
+                let p = self.bind(BindValue::String(s.to_string()));
+                match self.dialect {
+                    Dialect::Postgres => format!("{p} = ANY({col})"),
+                    Dialect::SurrealQl => format!("{col} CONTAINS {p}"),
+                }
+            }
+            (ItemValue::CmpInt { eq, bigger, value }, ColumnKind::Scalar) => {
+                let p = self.bind(BindValue::Int(*value));
+                format!("{col} {} {p}", operator(*eq, *bigger))
+            }
+            (ItemValue::CmpFloat { eq, bigger, value }, ColumnKind::Scalar) => {
+                let p = self.bind(BindValue::Float(*value as f64));
+                format!("{col} {} {p}", operator(*eq, *bigger))
+            }
+            (ItemValue::Date(query), ColumnKind::Scalar) => {
+                let mut conditions = vec![];
+                let (start, end) = query.bounds(self.today).unwrap_or_default();
+                if let Some(start) = start {
+                    let p = self.bind(BindValue::Date(start));
+                    conditions.push(format!("{col} >= {p}"));
+                }
+                if let Some(end) = end {
+                    let p = self.bind(BindValue::Date(end));
+                    conditions.push(format!("{col} < {p}"));
+                }
+                match conditions.is_empty() {
+                    true => "false".to_string(),
+                    false => format!("({})", conditions.join(" AND ")),
+                }
+            }
+            (ItemValue::Bool(v), ColumnKind::Scalar | ColumnKind::Bool) => {
+                self.equals(col, BindValue::Bool(*v))
+            }
+            (ItemValue::Int(v), ColumnKind::Scalar) => self.equals(col, BindValue::Int(*v)),
+            (ItemValue::Float(v), ColumnKind::Scalar) => self.equals(col, BindValue::Float(*v)),
+            (ItemValue::String(v), ColumnKind::Scalar) => {
+                self.equals(col, BindValue::String(v.to_string()))
+            }
+            (ItemValue::Set(values), _) => match values.is_empty() {
+                true => "false".to_string(),
+                false => {
+                    let conditions = values
+                        .iter()
+                        .map(|value| self.condition(name, column, value))
+                        .collect::<Result<Vec<_>, _>>()?;
+                    format!("({})", conditions.join(" OR "))
+                }
+            },
+            (
+                ItemValue::Range {
+                    min,
+                    max,
+                    inclusive,
+                },
+                ColumnKind::Scalar,
+            ) => match (scalar(min), scalar(max)) {
+                (Some(min), Some(max)) => {
+                    let min = self.bind(min);
+                    let max = self.bind(max);
+                    let operator = match inclusive {
+                        true => "<=",
+                        false => "<",
+                    };
+                    format!("({col} >= {min} AND {col} {operator} {max})")
+                }
+                _ => return Err(unsupported(name, value)),
+            },
+            (value, _) => return Err(unsupported(name, value)),
+        })
+    }
+}
+
+fn scalar(value: &ItemValue) -> Option<BindValue> {
+    match value {
+        ItemValue::Int(v) => Some(BindValue::Int(*v)),
+        ItemValue::Float(v) => Some(BindValue::Float(*v)),
+        _ => None,
+    }
+}
+
+fn unsupported(name: &str, value: &ItemValue) -> ApiErr {
+    ApiErr {
+        message: Some(format!("Field {name} cannot be searched with {value}")),
+        cause: None,
+        err_type: ApiErrorType::InvalidInput,
+    }
+}
+
+fn operator(eq: bool, bigger: bool) -> &'static str {
+    match (bigger, eq) {
+        (true, true) => ">=",
+        (true, false) => ">",
+        (false, true) => "<=",
+        (false, false) => "<",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::search::parser::parse_query;
+    use crate::search::schema::SearchSchema;
+    use crate::search::test_util::{item, range};
+
+    fn mapping() -> ColumnMapping {
+        ColumnMapping::default()
+            .with_field("title", "title", ColumnKind::Text)
+            .with_field("tag", "tags", ColumnKind::Array)
+            .with_field("status", "status", ColumnKind::Scalar)
+            .with_field("rating", "rating", ColumnKind::Scalar)
+            .with_field("chapters", "chapters", ColumnKind::Scalar)
+            .with_field("favorite", "favorite", ColumnKind::Bool)
+            .with_field("last_read", "last_read", ColumnKind::Scalar)
+            .with_order(Order::Created, "created_at")
+    }
+
+    fn today() -> NaiveDate {
+        NaiveDate::from_ymd_opt(2024, 3, 31).unwrap()
+    }
+
+    fn date(y: i32, m: u32, d: u32) -> BindValue {
+        BindValue::Date(NaiveDate::from_ymd_opt(y, m, d).unwrap())
+    }
+
+    fn compile(query: &str, dialect: Dialect) -> Result<(String, Vec<BindValue>), ApiErr> {
+        let query = parse_query(query, &SearchSchema::internal().fields).unwrap();
+        compile_query(&query, &mapping(), dialect, today())
+    }
+
+    fn request(query: &str, limit: u32, page: u32) -> SearchRequest {
+        SearchRequest {
+            order: Order::Created,
+            desc: true,
+            then: vec![],
+            seed: None,
+            limit,
+            page,
+            query: parse_query(query, &SearchSchema::internal().fields).unwrap(),
+            facets: vec![],
+            highlight: false,
+        }
+    }
+
+    fn string(s: &str) -> BindValue {
+        BindValue::String(s.to_string())
+    }
+
+    const QUERY: &str = r#"and:(title:"50%_off\\" tag:!gore or:(rating:>=4.5 chapters:<10))"#;
+
+    #[test]
+    fn compiles_postgres() {
+        assert_eq!(
+            compile(QUERY, Dialect::Postgres).unwrap(),
+            (
+                "(title ILIKE $1 AND NOT ($2 = ANY(tags)) AND (rating >= $3 OR chapters < $4))"
+                    .to_string(),
+                vec![
+                    string(r"%50\%\_off\\%"),
+                    string("gore"),
+                    BindValue::Float(4.5),
+                    BindValue::Int(10),
+                ]
+            )
+        );
+    }
+
+    #[test]
+    fn compiles_surrealql() {
+        assert_eq!(
+            compile(QUERY, Dialect::SurrealQl).unwrap(),
+            (
+                "(string::contains(string::lowercase(title), $p1) AND NOT (tags CONTAINS $p2) \
+                 AND (rating >= $p3 OR chapters < $p4))"
+                    .to_string(),
+                vec![
+                    string(r"50%_off\"),
+                    string("gore"),
+                    BindValue::Float(4.5),
+                    BindValue::Int(10),
+                ]
+            )
+        );
+    }
+
+    #[test]
+    fn values_are_only_bound() {
+        let query = r#"status:"x' OR 1=1; --" title:"$1""#;
+        assert_eq!(
+            compile(query, Dialect::Postgres).unwrap(),
+            (
+                "(status = $1 AND title ILIKE $2)".to_string(),
+                vec![string("x' OR 1=1; --"), string("%$1%")]
+            )
+        );
+    }
+
+    #[test]
+    fn compiles_empty_arrays() {
+        for dialect in [Dialect::Postgres, Dialect::SurrealQl] {
+            assert_eq!(
+                compile("and:()", dialect).unwrap(),
+                ("true".to_string(), vec![])
+            );
+            assert_eq!(
+                compile("or:()", dialect).unwrap(),
+                ("false".to_string(), vec![])
+            );
+            assert_eq!(
+                compile("or:(and:() or:()) rating:!", dialect).unwrap().0,
+                match dialect {
+                    Dialect::Postgres => "((true OR false) AND NOT (rating IS NOT NULL))",
+                    Dialect::SurrealQl => "((true OR false) AND NOT (rating != NONE))",
+                }
+            );
+        }
+    }
+
+    #[test]
+    fn compiles_valueless_bools_as_true() {
+        // same query as the matcher test `valueless_bools_match_true`
+        assert_eq!(
+            compile("fav: or:(fav:! rating:)", Dialect::Postgres).unwrap(),
+            (
+                "(favorite = $1 AND (NOT (favorite = $2) OR rating IS NOT NULL))".to_string(),
+                vec![BindValue::Bool(true), BindValue::Bool(true)]
+            )
+        );
+        assert_eq!(
+            compile("favorite:false", Dialect::SurrealQl).unwrap(),
+            ("favorite = $p1".to_string(), vec![BindValue::Bool(false)])
+        );
+    }
+
+    #[test]
+    fn resolves_dates_against_today() {
+        assert_eq!(
+            compile("lr:>=-1mo lr:!2023..2024", Dialect::Postgres).unwrap(),
+            (
+                "((last_read >= $1) AND NOT ((last_read >= $2 AND last_read < $3)))".to_string(),
+                vec![date(2024, 2, 29), date(2023, 1, 1), date(2025, 1, 1)]
+            )
+        );
+        assert_eq!(
+            compile("lr:+999999999y", Dialect::SurrealQl).unwrap(),
+            ("false".to_string(), vec![])
+        );
+    }
+
+    #[test]
+    fn rejects_unsupported_items() {
+        let err = compile("author:x", Dialect::Postgres).unwrap_err();
+        assert_eq!(err.message(), "Field author cannot be searched");
+        let query = item(
+            false,
+            "tag",
+            range(ItemValue::Int(1), ItemValue::Int(2), false),
+        );
+        let err = compile_query(&query, &mapping(), Dialect::Postgres, today()).unwrap_err();
+        assert_eq!(err.message(), "Field tag cannot be searched with 1..2");
+    }
+
+    #[test]
+    fn paginates() {
+        let compiled = request("and:()", 20, 3)
+            .compile(&mapping(), Dialect::Postgres, today())
+            .unwrap();
+        assert_eq!((compiled.limit, compiled.offset), (20, 40));
+        assert_eq!(
+            compiled.to_string(),
+            "WHERE true ORDER BY created_at DESC LIMIT 20 OFFSET 40"
+        );
+        let compiled = request("and:()", 20, 0)
+            .compile(&mapping(), Dialect::SurrealQl, today())
+            .unwrap();
+        assert_eq!(
+            compiled.to_string(),
+            "WHERE true ORDER BY created_at DESC LIMIT 20 START 0"
+        );
+        let compiled = request("and:()", u32::MAX, u32::MAX)
+            .compile(&mapping(), Dialect::Postgres, today())
+            .unwrap();
+        assert_eq!(compiled.offset, (u32::MAX as u64 - 1) * u32::MAX as u64);
+    }
+
+    #[test]
+    fn orders_random_with_seed() {
+        let mut request = request("and:()", 10, 1);
+        request.order = Order::Random;
+        let compiled = request
+            .compile(&mapping(), Dialect::Postgres, today())
+            .unwrap();
+        assert_eq!(compiled.order_by.as_deref(), Some("RANDOM()"));
+        request.seed = Some(7);
+        let err = request
+            .compile(&mapping(), Dialect::Postgres, today())
+            .unwrap_err();
+        assert_eq!(err.cause.as_deref(), Some("7"));
+        let mapping = mapping().with_order(Order::Random, "id");
+        let compiled = request
+            .compile(&mapping, Dialect::Postgres, today())
+            .unwrap();
+        assert_eq!(
+            compiled.order_by.as_deref(),
+            Some("md5(id::text || '7') DESC")
+        );
+        let compiled = request
+            .compile(&mapping, Dialect::SurrealQl, today())
+            .unwrap();
+        assert_eq!(
+            compiled.order_by.as_deref(),
+            Some("crypto::md5(string::concat(id, '7')) DESC")
+        );
+    }
+}
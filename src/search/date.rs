@@ -0,0 +1,356 @@
+use chrono::{Datelike, Days, Months, NaiveDate};
+use serde::{Deserialize, Serialize};
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+/// date filter of the search language
+///
+/// - `2024-01-01`, `2024-01`, `2024` match every date in that day, month or year
+/// - `-7d`, `+1w`, `-1mo`, `-2y` are relative to today
+/// - `>=-1mo`, `<2024` compare like [`crate::search::ItemValue::CmpInt`]
+/// - `2023..2024` matches everything from the start of the first to the end of the second
+#[derive(Serialize, Deserialize, Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum DateQuery {
+    On(DatePoint),
+    Cmp {
+        eq: bool,
+        bigger: bool,
+        point: DatePoint,
+    },
+    Range {
+        from: DatePoint,
+        to: DatePoint,
+    },
+}
+
+#[derive(Serialize, Deserialize, Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum DatePoint {
+    Absolute {
+        date: NaiveDate,
+        precision: DatePrecision,
+    },
+    Relative {
+        amount: i64,
+        unit: DateUnit,
+    },
+}
+
+#[derive(Serialize, Deserialize, Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum DatePrecision {
+    Year,
+    Month,
+    Day,
+}
+
+#[derive(Serialize, Deserialize, Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum DateUnit {
+    Day,
+    Week,
+    Month,
+    Year,
+}
+
+impl DateUnit {
+    fn suffix(&self) -> &'static str {
+        match self {
+            DateUnit::Day => "d",
+            DateUnit::Week => "w",
+            DateUnit::Month => "mo",
+            DateUnit::Year => "y",
+        }
+    }
+}
+
+impl DatePoint {
+    /// half open interval `[start, end)` of days covered by the point
+    pub fn interval(&self, today: NaiveDate) -> Option<(NaiveDate, NaiveDate)> {
+        let (start, precision) = match self {
+            DatePoint::Absolute { date, precision } => (*date, *precision),
+            DatePoint::Relative { amount, unit } => {
+                let date = match unit {
+                    DateUnit::Day => shift_days(today, *amount),
+                    DateUnit::Week => shift_days(today, amount.checked_mul(7)?),
+                    DateUnit::Month => shift_months(today, *amount),
+                    DateUnit::Year => shift_months(today, amount.checked_mul(12)?),
+                }?;
+                (date, DatePrecision::Day)
+            }
+        };
+        let end = match precision {
+            DatePrecision::Year => NaiveDate::from_ymd_opt(start.year() + 1, 1, 1),
+            DatePrecision::Month => start.checked_add_months(Months::new(1)),
+            DatePrecision::Day => start.checked_add_days(Days::new(1)),
+        }?;
+        Some((start, end))
+    }
+}
+
+fn shift_days(date: NaiveDate, n: i64) -> Option<NaiveDate> {
+    let days = Days::new(n.unsigned_abs());
+    match n >= 0 {
+        true => date.checked_add_days(days),
+        false => date.checked_sub_days(days),
+    }
+}
+
+fn shift_months(date: NaiveDate, n: i64) -> Option<NaiveDate> {
+    let months = Months::new(u32::try_from(n.unsigned_abs()).ok()?);
+    match n >= 0 {
+        true => date.checked_add_months(months),
+        false => date.checked_sub_months(months),
+    }
+}
+
+impl DateQuery {
+    /// half open interval `[start, end)` where `None` means unbounded,
+    /// returns `None` if a relative date is out of range
+    pub fn bounds(&self, today: NaiveDate) -> Option<(Option<NaiveDate>, Option<NaiveDate>)> {
+        Some(match self {
+            DateQuery::On(point) => {
+                let (start, end) = point.interval(today)?;
+                (Some(start), Some(end))
+            }
+            DateQuery::Cmp { eq, bigger, point } => {
+                let (start, end) = point.interval(today)?;
+                match (bigger, eq) {
+                    (true, true) => (Some(start), None),
+                    (true, false) => (Some(end), None),
+                    (false, true) => (None, Some(end)),
+                    (false, false) => (None, Some(start)),
+                }
+            }
+            DateQuery::Range { from, to } => {
+                (Some(from.interval(today)?.0), Some(to.interval(today)?.1))
+            }
+        })
+    }
+
+    pub fn matches(&self, date: NaiveDate, today: NaiveDate) -> bool {
+        match self.bounds(today) {
+            Some((start, end)) => {
+                start.map_or(true, |start| date >= start) && end.map_or(true, |end| date < end)
+            }
+            None => false,
+        }
+    }
+}
+
+impl Display for DatePoint {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DatePoint::Absolute { date, precision } => match precision {
+                DatePrecision::Year => write!(f, "{:04}", date.year()),
+                DatePrecision::Month => write!(f, "{:04}-{:02}", date.year(), date.month()),
+                DatePrecision::Day => write!(f, "{}", date.format("%Y-%m-%d")),
+            },
+            DatePoint::Relative { amount, unit } => write!(f, "{amount:+}{}", unit.suffix()),
+        }
+    }
+}
+
+impl Display for DateQuery {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DateQuery::On(point) => write!(f, "{point}"),
+            DateQuery::Cmp { eq, bigger, point } => write!(
+                f,
+                "{}{}{point}",
+                match bigger {
+                    true => ">",
+                    false => "<",
+                },
+                match eq {
+                    true => "=",
+                    false => "",
+                }
+            ),
+            DateQuery::Range { from, to } => write!(f, "{from}..{to}"),
+        }
+    }
+}
+
+impl FromStr for DatePoint {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let err = || format!("Failed to parse date: {}", s);
+        if s.starts_with(['+', '-']) {
+            let unit = [
+                DateUnit::Month,
+                DateUnit::Day,
+                DateUnit::Week,
+                DateUnit::Year,
+            ]
+            .into_iter()
+            .find(|unit| s.ends_with(unit.suffix()))
+            .ok_or_else(err)?;
+            let amount = s[..s.len() - unit.suffix().len()]
+                .parse()
+                .map_err(|_| err())?;
+            return Ok(DatePoint::Relative { amount, unit });
+        }
+        let parts = s
+            .split('-')
+            .map(|v| v.parse::<u32>().map_err(|_| err()))
+            .collect::<Result<Vec<_>, _>>()?;
+        let (date, precision) = match parts.as_slice() {
+            [y] => (
+                NaiveDate::from_ymd_opt(*y as i32, 1, 1),
+                DatePrecision::Year,
+            ),
+            [y, m] => (
+                NaiveDate::from_ymd_opt(*y as i32, *m, 1),
+                DatePrecision::Month,
+            ),
+            [y, m, d] => (
+                NaiveDate::from_ymd_opt(*y as i32, *m, *d),
+                DatePrecision::Day,
+            ),
+            _ => return Err(err()),
+        };
+        Ok(DatePoint::Absolute {
+            date: date.ok_or_else(err)?,
+            precision,
+        })
+    }
+}
+
+impl FromStr for DateQuery {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Some((from, to)) = s.split_once("..") {
+            return Ok(DateQuery::Range {
+                from: from.parse()?,
+                to: to.parse()?,
+            });
+        }
+        let (bigger, rest) = match (s.strip_prefix('>'), s.strip_prefix('<')) {
+            (Some(rest), _) => (true, rest),
+            (_, Some(rest)) => (false, rest),
+            _ => return Ok(DateQuery::On(s.parse()?)),
+        };
+        let (eq, rest) = match rest.strip_prefix('=') {
+            Some(rest) => (true, rest),
+            None => (false, rest),
+        };
+        Ok(DateQuery::Cmp {
+            eq,
+            bigger,
+            point: rest.parse()?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    fn today() -> NaiveDate {
+        date(2024, 3, 31)
+    }
+
+    fn bounds(s: &str) -> Option<(Option<NaiveDate>, Option<NaiveDate>)> {
+        s.parse::<DateQuery>().unwrap().bounds(today())
+    }
+
+    #[test]
+    fn display_round_trips() {
+        for s in [
+            "2024",
+            "2024-02",
+            "2024-02-29",
+            "-1mo",
+            "+7d",
+            "-2w",
+            "-1y",
+            ">=-1mo",
+            "<2024",
+            ">2024-02",
+            "<=2024-02-29",
+            "2023..2024",
+            "2023-05..-1w",
+        ] {
+            assert_eq!(
+                s.parse::<DateQuery>().map(|v| v.to_string()),
+                Ok(s.to_string())
+            );
+        }
+        assert_eq!(
+            "-1mo".parse::<DateQuery>(),
+            Ok(DateQuery::On(DatePoint::Relative {
+                amount: -1,
+                unit: DateUnit::Month,
+            }))
+        );
+        assert_eq!(
+            "2024-1-5".parse::<DateQuery>().map(|v| v.to_string()),
+            Ok("2024-01-05".to_string())
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_dates() {
+        for s in [
+            "",
+            "abc",
+            "2023-02-30",
+            "2024-13",
+            "2024-01-01-01",
+            "-1x",
+            "-mo",
+            "7d",
+            ">=",
+            "2023..",
+            "..2024",
+            "2023..2024..2025",
+        ] {
+            assert!(s.parse::<DateQuery>().is_err(), "{s}");
+        }
+        assert_eq!(
+            "2023-02-30".parse::<DateQuery>(),
+            Err("Failed to parse date: 2023-02-30".to_string())
+        );
+    }
+
+    #[test]
+    fn resolves_bounds() {
+        assert_eq!(
+            bounds("-1mo"),
+            Some((Some(date(2024, 2, 29)), Some(date(2024, 3, 1))))
+        );
+        assert_eq!(bounds(">=-1mo"), Some((Some(date(2024, 2, 29)), None)));
+        assert_eq!(bounds(">-7d"), Some((Some(date(2024, 3, 25)), None)));
+        assert_eq!(bounds("<2024"), Some((None, Some(date(2024, 1, 1)))));
+        assert_eq!(bounds("<=2024-02"), Some((None, Some(date(2024, 3, 1)))));
+        assert_eq!(
+            bounds("2023..2024"),
+            Some((Some(date(2023, 1, 1)), Some(date(2025, 1, 1))))
+        );
+        assert_eq!(
+            bounds("2024-02..-1d"),
+            Some((Some(date(2024, 2, 1)), Some(date(2024, 3, 31))))
+        );
+        assert_eq!(bounds("+999999999y"), None);
+    }
+
+    #[test]
+    fn matches_half_open_interval() {
+        let query = "2023..2024".parse::<DateQuery>().unwrap();
+        assert!(!query.matches(date(2022, 12, 31), today()));
+        assert!(query.matches(date(2023, 1, 1), today()));
+        assert!(query.matches(date(2024, 12, 31), today()));
+        assert!(!query.matches(date(2025, 1, 1), today()));
+        let query = ">=-1mo".parse::<DateQuery>().unwrap();
+        assert!(query.matches(date(2024, 2, 29), today()));
+        assert!(!query.matches(date(2024, 2, 29), date(2024, 4, 1)));
+        assert!(!"+999999999y"
+            .parse::<DateQuery>()
+            .unwrap()
+            .matches(today(), today()));
+    }
+}
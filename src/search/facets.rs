@@ -0,0 +1,49 @@
+use crate::search::{SearchResponse, Status};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// number of matches per value of a field for the whole query, not only the current page
+#[derive(Serialize, Deserialize, Debug, Default, Clone, Eq, PartialEq)]
+pub struct SearchFacets {
+    #[serde(default)]
+    pub status: HashMap<Status, u64>,
+    #[serde(default)]
+    pub tag: HashMap<String, u64>,
+    #[serde(default)]
+    pub kind: HashMap<String, u64>,
+    /// every other requested field
+    #[serde(default)]
+    pub other: HashMap<String, HashMap<String, u64>>,
+}
+
+/// response of a [`crate::search::SearchRequest`] with facets
+#[derive(Serialize, Deserialize, Debug)]
+pub struct FacetedSearchResponse {
+    pub results: Vec<SearchResponse>,
+    pub facets: SearchFacets,
+}
+
+impl SearchFacets {
+    /// counts the facets of locally cached entries
+    ///
+    /// only status and tag are part of [`SearchResponse`], other facets stay empty
+    pub fn count(entries: &[&SearchResponse], facets: &[String]) -> Self {
+        let mut out = Self::default();
+        for facet in facets {
+            match facet.as_str() {
+                "status" => {
+                    for entry in entries {
+                        *out.status.entry(entry.status).or_default() += 1;
+                    }
+                }
+                "tag" => {
+                    for tag in entries.iter().flat_map(|entry| &entry.tags) {
+                        *out.tag.entry(tag.clone()).or_default() += 1;
+                    }
+                }
+                _ => {}
+            }
+        }
+        out
+    }
+}
@@ -0,0 +1,323 @@
+use crate::search::parser::edit_distance;
+use std::collections::HashMap;
+use unicode_normalization::UnicodeNormalization;
+
+/// best title of one language for a query
+#[derive(Debug, Clone, PartialEq)]
+pub struct TitleMatch {
+    pub language: String,
+    pub title: String,
+    /// between 0 and 1
+    pub score: f32,
+}
+
+/// fuzzy matches a query against titles in any language and script
+///
+/// both sides get folded to NFKC normalized lowercase text without punctuation,
+/// kana is additionally compared as romaji
+pub struct TitleMatcher {
+    query: Folded,
+}
+
+struct Folded {
+    text: String,
+    romaji: String,
+}
+
+impl Folded {
+    fn new(s: &str) -> Self {
+        let text = fold(s);
+        let romaji = fold_romaji(&to_romaji(&text));
+        Self { text, romaji }
+    }
+}
+
+impl TitleMatcher {
+    pub fn new(query: &str) -> Self {
+        Self {
+            query: Folded::new(query),
+        }
+    }
+
+    pub fn score(&self, title: &str) -> f32 {
+        let title = Folded::new(title);
+        let compact = |s: &str| s.replace(' ', "");
+        score(&self.query.text, &title.text)
+            .max(score(&self.query.romaji, &title.romaji))
+            .max(score(&compact(&self.query.romaji), &compact(&title.romaji)))
+    }
+
+    /// best title per language with a score of at least `min_score`, sorted by score
+    pub fn best(&self, titles: &HashMap<String, Vec<String>>, min_score: f32) -> Vec<TitleMatch> {
+        let mut matches = titles
+            .iter()
+            .filter_map(|(language, titles)| {
+                titles
+                    .iter()
+                    .map(|title| (self.score(title), title))
+                    .filter(|(score, _)| *score >= min_score)
+                    .max_by(|(a, _), (b, _)| a.total_cmp(b))
+                    .map(|(score, title)| TitleMatch {
+                        language: language.clone(),
+                        title: title.clone(),
+                        score,
+                    })
+            })
+            .collect::<Vec<_>>();
+        matches.sort_by(|a, b| {
+            b.score
+                .total_cmp(&a.score)
+                .then(a.language.cmp(&b.language))
+        });
+        matches
+    }
+}
+
+/// NFKC normalized, lowercase, katakana to hiragana and punctuation to spaces
+///
+/// NFKC turns fullwidth ascii into ascii, halfwidth kana into fullwidth kana
+/// and splits ligatures like `ﬁ`
+pub fn fold(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for c in s.nfkc() {
+        let c = match c as u32 {
+            // katakana to hiragana
+            0x30A1..=0x30F6 => char::from_u32(c as u32 - 0x60).unwrap_or(c),
+            _ => c,
+        };
+        if c.is_alphanumeric() || c == 'ー' {
+            out.extend(c.to_lowercase());
+        } else if !out.ends_with(' ') {
+            out.push(' ');
+        }
+    }
+    out.trim().to_string()
+}
+
+/// hepburn romanization of hiragana, everything else is kept
+fn to_romaji(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    let mut double = false;
+    for c in s.chars() {
+        let romaji = match c {
+            'っ' => {
+                double = true;
+                continue;
+            }
+            'ゃ' | 'ゅ' | 'ょ' => {
+                let vowel = match c {
+                    'ゃ' => 'a',
+                    'ゅ' => 'u',
+                    _ => 'o',
+                };
+                if out.ends_with('i') {
+                    out.pop();
+                    if !(out.ends_with("sh") || out.ends_with("ch") || out.ends_with('j')) {
+                        out.push('y');
+                    }
+                } else {
+                    out.push('y');
+                }
+                out.push(vowel);
+                continue;
+            }
+            // ちぇ is che and ふぁ is fa
+            'ぁ' | 'ぃ' | 'ぅ' | 'ぇ' | 'ぉ'
+                if out.ends_with("fu")
+                    || ((out.ends_with("shi") || out.ends_with("chi") || out.ends_with("ji"))
+                        && c == 'ぇ') =>
+            {
+                out.pop();
+                out.extend(kana(c));
+                continue;
+            }
+            'ー' => {
+                if let Some(vowel) = out.chars().last().filter(|c| "aiueo".contains(*c)) {
+                    out.push(vowel);
+                }
+                continue;
+            }
+            c => kana(c),
+        };
+        match romaji {
+            Some(romaji) => {
+                if double {
+                    if let Some(first) = romaji.chars().next().filter(|c| !"aiueon".contains(*c)) {
+                        out.push(first);
+                    }
+                }
+                out.push_str(romaji);
+            }
+            None => out.push(c),
+        }
+        double = false;
+    }
+    out
+}
+
+/// folds romanization variants like long vowels and macrons
+fn fold_romaji(s: &str) -> String {
+    let s = s
+        .chars()
+        .map(|c| match c {
+            'ā' | 'â' => 'a',
+            'ī' | 'î' => 'i',
+            'ū' | 'û' => 'u',
+            'ē' | 'ê' => 'e',
+            'ō' | 'ô' => 'o',
+            c => c,
+        })
+        .collect::<String>();
+    s.replace("ou", "o")
+        .replace("oo", "o")
+        .replace("uu", "u")
+        .replace("aa", "a")
+        .replace("ee", "e")
+}
+
+fn kana(c: char) -> Option<&'static str> {
+    const ROWS: [(&str, [&str; 5]); 16] = [
+        ("あいうえお", ["a", "i", "u", "e", "o"]),
+        ("かきくけこ", ["ka", "ki", "ku", "ke", "ko"]),
+        ("がぎぐげご", ["ga", "gi", "gu", "ge", "go"]),
+        ("さしすせそ", ["sa", "shi", "su", "se", "so"]),
+        ("ざじずぜぞ", ["za", "ji", "zu", "ze", "zo"]),
+        ("たちつてと", ["ta", "chi", "tsu", "te", "to"]),
+        ("だぢづでど", ["da", "ji", "zu", "de", "do"]),
+        ("なにぬねの", ["na", "ni", "nu", "ne", "no"]),
+        ("はひふへほ", ["ha", "hi", "fu", "he", "ho"]),
+        ("ばびぶべぼ", ["ba", "bi", "bu", "be", "bo"]),
+        ("ぱぴぷぺぽ", ["pa", "pi", "pu", "pe", "po"]),
+        ("まみむめも", ["ma", "mi", "mu", "me", "mo"]),
+        ("や_ゆ_よ", ["ya", "", "yu", "", "yo"]),
+        ("らりるれろ", ["ra", "ri", "ru", "re", "ro"]),
+        ("わゐ_ゑを", ["wa", "wi", "", "we", "wo"]),
+        ("ぁぃぅぇぉ", ["a", "i", "u", "e", "o"]),
+    ];
+    if c == 'ん' {
+        return Some("n");
+    }
+    ROWS.iter().find_map(|(kana, romaji)| {
+        kana.chars()
+            .position(|k| k == c)
+            .map(|i| romaji[i])
+            .filter(|v| !v.is_empty())
+    })
+}
+
+/// 1 if the title contains the query, otherwise the averaged similarity of the query tokens
+/// to their most similar title token
+fn score(query: &str, title: &str) -> f32 {
+    if query.is_empty() || title.is_empty() {
+        return 0.0;
+    }
+    if title.contains(query) {
+        return 1.0;
+    }
+    let title_tokens = title.split(' ').collect::<Vec<_>>();
+    let query_tokens = query.split(' ').collect::<Vec<_>>();
+    let total = query_tokens
+        .iter()
+        .map(|query| {
+            title_tokens
+                .iter()
+                .map(|title| match title.starts_with(query) {
+                    true => 0.9,
+                    false => similarity(query, title),
+                })
+                .fold(0.0, f32::max)
+        })
+        .sum::<f32>();
+    total / query_tokens.len() as f32
+}
+
+fn similarity(a: &str, b: &str) -> f32 {
+    let len = a.chars().count().max(b.chars().count());
+    if len == 0 {
+        return 1.0;
+    }
+    1.0 - edit_distance(a, b) as f32 / len as f32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn score(query: &str, title: &str) -> f32 {
+        TitleMatcher::new(query).score(title)
+    }
+
+    #[test]
+    fn folds_compatibility_characters() {
+        assert_eq!(fold("ＯＮＥ　ＰＩＥＣＥ！"), "one piece");
+        assert_eq!(fold("ﾍﾞﾙｾﾙｸ"), "べるせるく");
+        assert_eq!(fold("ﬁre ﬂame"), "fire flame");
+        assert_eq!(fold("Kaguya-sama: Love is War"), "kaguya sama love is war");
+        assert_eq!(fold("ﾁｪﾝｿｰﾏﾝ"), "ちぇんそーまん");
+        assert_eq!(score("ﾍﾞﾙｾﾙｸ", "ベルセルク"), 1.0);
+        assert_eq!(score("ﬁre punch", "Fire Punch"), 1.0);
+        assert_eq!(score("ｏｎｅ ｐｉｅｃｅ", "One Piece"), 1.0);
+    }
+
+    #[test]
+    fn romanizes_kana() {
+        assert_eq!(to_romaji("しんげきのきょじん"), "shingekinokyojin");
+        assert_eq!(to_romaji("がっこう"), "gakkou");
+        assert_eq!(to_romaji("じゃしん ちゃん"), "jashin chan");
+        assert_eq!(to_romaji("ちぇんそーまん"), "chensooman");
+        assert_eq!(to_romaji("ふぁいあ ぱんち"), "faia panchi");
+        assert_eq!(fold_romaji("toukyou"), "tokyo");
+        assert_eq!(fold_romaji("tōkyō"), "tokyo");
+    }
+
+    #[test]
+    fn scores_across_scripts() {
+        assert_eq!(score("shingeki no kyojin", "しんげき の きょじん"), 1.0);
+        assert_eq!(score("Shingeki no Kyojin", "シンゲキノキョジン"), 1.0);
+        assert_eq!(score("tokyo ghoul", "Tōkyō Ghoul"), 1.0);
+        assert_eq!(score("とうきょう", "Tokyo Revengers"), 1.0);
+        assert_eq!(score("gakko", "がっこうぐらし"), 1.0);
+        assert_eq!(score("onepiece", "One Piece"), 1.0);
+    }
+
+    #[test]
+    fn scores_partial_matches() {
+        assert_eq!(score("one", "One Piece"), 1.0);
+        assert_eq!(score("pie one", "One Piece"), 0.9);
+        let typo = score("berserc", "Berserk");
+        assert!((typo - 6.0 / 7.0).abs() < 1e-6, "{typo}");
+        assert!(score("naruto", "Bleach") < 0.5);
+        assert_eq!(score("", "Bleach"), 0.0);
+        assert_eq!(score("!!", "Bleach"), 0.0);
+    }
+
+    #[test]
+    fn returns_best_title_per_language() {
+        let titles = HashMap::from([
+            (
+                "en".to_string(),
+                vec!["Attack on Titan".to_string(), "AoT".to_string()],
+            ),
+            ("ja".to_string(), vec!["進撃の巨人".to_string()]),
+            ("ja-ro".to_string(), vec!["Shingeki no Kyojin".to_string()]),
+        ]);
+        let matches = TitleMatcher::new("shingeki").best(&titles, 0.5);
+        assert_eq!(
+            matches,
+            vec![TitleMatch {
+                language: "ja-ro".to_string(),
+                title: "Shingeki no Kyojin".to_string(),
+                score: 1.0,
+            }]
+        );
+        let matches = TitleMatcher::new("atack titan").best(&titles, 0.0);
+        assert_eq!(
+            matches
+                .iter()
+                .map(|v| v.language.as_str())
+                .collect::<Vec<_>>(),
+            vec!["en", "ja-ro", "ja"]
+        );
+        assert_eq!(matches[0].title, "Attack on Titan");
+    }
+}
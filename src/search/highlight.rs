@@ -0,0 +1,197 @@
+use crate::search::matcher::{FieldValue, Matcher, SearchFields};
+use crate::search::{Array, Item, ItemOrArray, ItemValue};
+use serde::{Deserialize, Serialize};
+use std::ops::Range;
+
+/// part of a field which matched the query
+#[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq)]
+pub struct Highlight {
+    /// canonical field name from the schema
+    pub field: String,
+    /// the matched string, e.g. one of the titles
+    pub value: String,
+    /// sorted and non overlapping ranges in chars, not bytes
+    pub ranges: Vec<Range<usize>>,
+}
+
+impl Matcher<'_> {
+    /// highlights for every positive string [`Item`] of the query,
+    /// negated items can't match and are skipped
+    pub fn highlights<T: SearchFields>(&self, entry: &T) -> Vec<Highlight> {
+        let mut out: Vec<Highlight> = vec![];
+        for item in positive_items(self.query()) {
+            let field = match entry.field(&item.data.name) {
+                Some(field) => field,
+                None => continue,
+            };
+            for (value, range) in find(&item.data.value, &field) {
+                match out
+                    .iter_mut()
+                    .find(|v| v.field == item.data.name && v.value == value)
+                {
+                    Some(highlight) => highlight.ranges.push(range),
+                    None => out.push(Highlight {
+                        field: item.data.name.clone(),
+                        value,
+                        ranges: vec![range],
+                    }),
+                }
+            }
+        }
+        for highlight in &mut out {
+            highlight.ranges = merge(std::mem::take(&mut highlight.ranges));
+        }
+        out
+    }
+}
+
+fn positive_items(query: &ItemOrArray) -> Vec<&Item> {
+    match query {
+        ItemOrArray::Item(item) if !item.not => vec![item],
+        ItemOrArray::Item(_) => vec![],
+        ItemOrArray::Array(Array { items, .. }) => items.iter().flat_map(positive_items).collect(),
+    }
+}
+
+fn find(value: &ItemValue, field: &FieldValue) -> Vec<(String, Range<usize>)> {
+    match (value, field) {
+        (ItemValue::String(s), FieldValue::Text(values)) => values
+            .iter()
+            .flat_map(|v| {
+                occurrences(v, s)
+                    .into_iter()
+                    .map(|range| (v.to_string(), range))
+            })
+            .collect(),
+        (ItemValue::String(s), FieldValue::Keyword(values)) => values
+            .iter()
+            .filter(|v| v.eq_ignore_ascii_case(s))
+            .map(|v| (v.to_string(), 0..v.chars().count()))
+            .collect(),
+        (ItemValue::Set(values), field) => values.iter().flat_map(|v| find(v, field)).collect(),
+        _ => vec![],
+    }
+}
+
+/// case insensitive occurrences of `needle` in `haystack`
+fn occurrences(haystack: &str, needle: &str) -> Vec<Range<usize>> {
+    let haystack = haystack.chars().collect::<Vec<_>>();
+    let needle = needle.chars().collect::<Vec<_>>();
+    if needle.is_empty() || needle.len() > haystack.len() {
+        return vec![];
+    }
+    let eq = |a: &char, b: &char| a.to_lowercase().eq(b.to_lowercase());
+    (0..=haystack.len() - needle.len())
+        .filter(|start| {
+            haystack[*start..]
+                .iter()
+                .zip(&needle)
+                .all(|(a, b)| eq(a, b))
+        })
+        .map(|start| start..start + needle.len())
+        .collect()
+}
+
+fn merge(mut ranges: Vec<Range<usize>>) -> Vec<Range<usize>> {
+    ranges.sort_by_key(|range| range.start);
+    let mut out: Vec<Range<usize>> = vec![];
+    for range in ranges {
+        match out.last_mut() {
+            Some(last) if range.start <= last.end => last.end = last.end.max(range.end),
+            _ => out.push(range),
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::search::parser::parse_query;
+    use crate::search::schema::SearchSchema;
+    use crate::search::Order;
+    use chrono::NaiveDate;
+    use std::borrow::Cow;
+
+    struct Entry {
+        titles: Vec<&'static str>,
+        tags: Vec<&'static str>,
+    }
+
+    impl SearchFields for Entry {
+        fn field(&self, name: &str) -> Option<FieldValue<'_>> {
+            let values =
+                |values: &[&'static str]| values.iter().map(|v| Cow::Borrowed(*v)).collect();
+            match name {
+                "title" => Some(FieldValue::Text(values(&self.titles))),
+                "tag" => Some(FieldValue::Keyword(values(&self.tags))),
+                _ => None,
+            }
+        }
+
+        fn sort_value(&self, _: Order) -> Option<FieldValue<'_>> {
+            None
+        }
+    }
+
+    fn highlights(query: &str) -> Vec<Highlight> {
+        let query = parse_query(query, &SearchSchema::internal().fields).unwrap();
+        let entry = Entry {
+            titles: vec!["Ōkami to Kōshinryō", "Spice and Wolf"],
+            tags: vec!["Romance", "Fantasy"],
+        };
+        Matcher::new(&query, NaiveDate::default()).highlights(&entry)
+    }
+
+    fn highlight(field: &str, value: &str, ranges: &[(usize, usize)]) -> Highlight {
+        Highlight {
+            field: field.to_string(),
+            value: value.to_string(),
+            ranges: ranges.iter().map(|(start, end)| *start..*end).collect(),
+        }
+    }
+
+    #[test]
+    fn counts_chars_and_folds_case() {
+        assert_eq!(
+            occurrences("Ōkami to Kōshinryō", "ō"),
+            vec![0..1, 10..11, 17..18]
+        );
+        assert_eq!(occurrences("ÄÖÜ äöü", "äö"), vec![0..2, 4..6]);
+        assert_eq!(occurrences("aaa", "aa"), vec![0..2, 1..3]);
+        assert_eq!(occurrences("a", "ab"), vec![]);
+        assert_eq!(occurrences("a", ""), vec![]);
+    }
+
+    #[test]
+    fn merges_overlapping_and_adjacent_ranges() {
+        assert_eq!(merge(vec![4..6, 0..2, 1..3]), vec![0..3, 4..6]);
+        assert_eq!(merge(vec![2..4, 0..2]), vec![0..4]);
+        assert_eq!(merge(vec![0..5, 1..2]), vec![0..5]);
+        assert_eq!(merge(vec![]), vec![]);
+    }
+
+    #[test]
+    fn highlights_text_and_keywords() {
+        assert_eq!(
+            highlights("and:(t:ō t:Kō t:wolf tag:ROMANCE tag:roman)"),
+            vec![
+                highlight("title", "Ōkami to Kōshinryō", &[(0, 1), (9, 11), (17, 18)]),
+                highlight("title", "Spice and Wolf", &[(10, 14)]),
+                highlight("tag", "Romance", &[(0, 7)]),
+            ]
+        );
+    }
+
+    #[test]
+    fn highlights_sets_and_skips_negated_items() {
+        assert_eq!(
+            highlights("or:(t:{spice,wolf} tag:{fantasy,horror} t:!ōkami tag:!romance)"),
+            vec![
+                highlight("title", "Spice and Wolf", &[(0, 5), (10, 14)]),
+                highlight("tag", "Fantasy", &[(0, 7)]),
+            ]
+        );
+        assert_eq!(highlights("t:!wolf"), vec![]);
+    }
+}
@@ -0,0 +1,96 @@
+use crate::scraper::ExternalSearchRequest;
+use crate::search::SearchRequest;
+use crate::RequestImpl;
+use serde::{Deserialize, Serialize};
+
+/// executed search
+#[derive(Serialize, Deserialize)]
+pub enum HistoryQuery {
+    Internal(SearchRequest),
+    External(ExternalSearchRequest),
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct SearchHistoryEntry {
+    pub id: String,
+    pub query: HistoryQuery,
+    /// text typed in the search bar, shown instead of the query if available
+    pub text: Option<String>,
+    /// timestamp in milliseconds of the last execution
+    pub last_used: u128,
+    /// how often the same query was executed
+    pub count: u32,
+}
+
+/// stores an executed search, ignored if the history is disabled
+///
+/// executing the same query again updates the existing entry
+#[derive(Serialize, Deserialize)]
+pub struct RecordSearchRequest {
+    pub query: HistoryQuery,
+    pub text: Option<String>,
+}
+
+impl RequestImpl for RecordSearchRequest {
+    const ROUTE: &'static str = "search/history/record";
+    const AUTH: bool = true;
+}
+
+#[derive(Serialize, Deserialize, Debug, Copy, Clone, Eq, PartialEq)]
+pub enum HistoryOrder {
+    /// last used first
+    Recent,
+    /// most used first
+    Frequent,
+}
+
+/// Response: [`SearchHistoryResponse`]
+#[derive(Serialize, Deserialize, Debug)]
+pub struct SearchHistoryRequest {
+    pub order: HistoryOrder,
+    pub limit: u32,
+}
+
+impl RequestImpl for SearchHistoryRequest {
+    const ROUTE: &'static str = "search/history";
+    const AUTH: bool = true;
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct SearchHistoryResponse {
+    pub entries: Vec<SearchHistoryEntry>,
+    /// see [`SearchHistorySettings`]
+    pub enabled: bool,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct DeleteSearchHistoryEntryRequest {
+    pub id: String,
+}
+
+impl RequestImpl for DeleteSearchHistoryEntryRequest {
+    const ROUTE: &'static str = "search/history/delete";
+    const AUTH: bool = true;
+}
+
+/// deletes every entry of the user
+#[derive(Serialize, Deserialize, Debug)]
+pub struct ClearSearchHistoryRequest;
+
+impl RequestImpl for ClearSearchHistoryRequest {
+    const ROUTE: &'static str = "search/history/clear";
+    const AUTH: bool = true;
+}
+
+/// Request and Response
+///
+/// disabling the history stops recording and deletes the existing entries
+#[derive(Serialize, Deserialize, Debug, Copy, Clone, Eq, PartialEq)]
+pub struct SearchHistorySettings {
+    pub enabled: bool,
+}
+
+impl RequestImpl for SearchHistorySettings {
+    const ROUTE: &'static str = "search/history/settings";
+    const AUTH: bool = true;
+}
@@ -0,0 +1,450 @@
+use crate::search::{
+    Array, Item, ItemOrArray, ItemValue, Order, OrderBy, SearchRequest, SearchResponse,
+};
+use chrono::NaiveDate;
+use std::borrow::Cow;
+use std::cmp::Ordering;
+
+/// value of a field of a cached entry
+pub enum FieldValue<'a> {
+    Bool(bool),
+    Int(i64),
+    Float(f64),
+    Date(NaiveDate),
+    /// free text, matched with contains
+    Text(Vec<Cow<'a, str>>),
+    /// fixed values like tags or status, matched with equals
+    Keyword(Vec<Cow<'a, str>>),
+}
+
+/// gives the [`Matcher`] access to the fields of an entry
+pub trait SearchFields {
+    /// value of the field with the canonical `name` from the schema
+    fn field(&self, name: &str) -> Option<FieldValue<'_>>;
+    /// value used to sort by `order`, entries without value are sorted last
+    fn sort_value(&self, order: Order) -> Option<FieldValue<'_>>;
+}
+
+impl SearchFields for SearchResponse {
+    fn field(&self, name: &str) -> Option<FieldValue<'_>> {
+        Some(match name {
+            "title" => FieldValue::Text(
+                self.titles
+                    .values()
+                    .flatten()
+                    .map(|v| Cow::Borrowed(v.as_str()))
+                    .collect(),
+            ),
+            "tag" => FieldValue::Keyword(
+                self.tags
+                    .iter()
+                    .map(|v| Cow::Borrowed(v.as_str()))
+                    .collect(),
+            ),
+            "status" => FieldValue::Keyword(vec![Cow::Owned(self.status.to_string())]),
+            _ => return None,
+        })
+    }
+
+    fn sort_value(&self, order: Order) -> Option<FieldValue<'_>> {
+        match order {
+            Order::Alphabetical => self
+                .titles
+                .values()
+                .flatten()
+                .map(|v| v.to_lowercase())
+                .min()
+                .map(|v| FieldValue::Text(vec![Cow::Owned(v)])),
+            _ => None,
+        }
+    }
+}
+
+/// evaluates a query in memory with the same semantics as the server
+pub struct Matcher<'a> {
+    query: &'a ItemOrArray,
+    today: NaiveDate,
+}
+
+impl<'a> Matcher<'a> {
+    /// relative dates are resolved against `today`
+    pub fn new(query: &'a ItemOrArray, today: NaiveDate) -> Self {
+        Self { query, today }
+    }
+
+    pub fn query(&self) -> &'a ItemOrArray {
+        self.query
+    }
+
+    pub fn matches<T: SearchFields>(&self, entry: &T) -> bool {
+        matches_query(self.query, entry, self.today)
+    }
+
+    /// filters, sorts and paginates `entries` like the server would answer `request`
+    pub fn search<'b, T: SearchFields>(
+        request: &SearchRequest,
+        entries: &'b [T],
+        today: NaiveDate,
+    ) -> Vec<&'b T> {
+        let matcher = Matcher::new(&request.query, today);
+        let mut hits = entries
+            .iter()
+            .filter(|entry| matcher.matches(*entry))
+            .collect::<Vec<_>>();
+        let keys = std::iter::once(OrderBy {
+            order: request.order,
+            desc: request.desc,
+        })
+        .chain(request.then.iter().copied())
+        .collect::<Vec<_>>();
+        sort_by(&mut hits, &keys);
+        let skip = request.page.saturating_sub(1) as usize * request.limit as usize;
+        hits.into_iter()
+            .skip(skip)
+            .take(request.limit as usize)
+            .collect()
+    }
+}
+
+/// stable sort, entries without a value for `order` stay at the end
+pub fn sort<T: SearchFields>(entries: &mut [&T], order: Order, desc: bool) {
+    sort_by(entries, &[OrderBy { order, desc }])
+}
+
+/// like [`sort`] but later keys are used if the previous ones are equal
+pub fn sort_by<T: SearchFields>(entries: &mut [&T], keys: &[OrderBy]) {
+    entries.sort_by(|a, b| {
+        keys.iter()
+            .map(|key| compare_key(*a, *b, key))
+            .find(|ordering| ordering.is_ne())
+            .unwrap_or(Ordering::Equal)
+    });
+}
+
+fn compare_key<T: SearchFields>(a: &T, b: &T, key: &OrderBy) -> Ordering {
+    match (a.sort_value(key.order), b.sort_value(key.order)) {
+        (Some(a), Some(b)) => {
+            let ordering = compare(&a, &b);
+            match key.desc {
+                true => ordering.reverse(),
+                false => ordering,
+            }
+        }
+        (Some(_), None) => Ordering::Less,
+        (None, Some(_)) => Ordering::Greater,
+        (None, None) => Ordering::Equal,
+    }
+}
+
+fn compare(a: &FieldValue, b: &FieldValue) -> Ordering {
+    match (a, b) {
+        (FieldValue::Bool(a), FieldValue::Bool(b)) => a.cmp(b),
+        (FieldValue::Int(a), FieldValue::Int(b)) => a.cmp(b),
+        (FieldValue::Date(a), FieldValue::Date(b)) => a.cmp(b),
+        (FieldValue::Text(a), FieldValue::Text(b))
+        | (FieldValue::Keyword(a), FieldValue::Keyword(b)) => a.cmp(b),
+        _ => match (as_f64(a), as_f64(b)) {
+            (Some(a), Some(b)) => a.total_cmp(&b),
+            _ => Ordering::Equal,
+        },
+    }
+}
+
+fn as_f64(value: &FieldValue) -> Option<f64> {
+    match value {
+        FieldValue::Int(v) => Some(*v as f64),
+        FieldValue::Float(v) => Some(*v),
+        _ => None,
+    }
+}
+
+fn value_f64(value: &ItemValue) -> Option<f64> {
+    match value {
+        ItemValue::Int(v) => Some(*v as f64),
+        ItemValue::Float(v) => Some(*v),
+        _ => None,
+    }
+}
+
+fn matches_query<T: SearchFields>(query: &ItemOrArray, entry: &T, today: NaiveDate) -> bool {
+    match query {
+        ItemOrArray::Item(item) => matches_item(item, entry, today),
+        ItemOrArray::Array(Array { or: true, items }) => {
+            items.iter().any(|item| matches_query(item, entry, today))
+        }
+        ItemOrArray::Array(Array { or: false, items }) => {
+            items.iter().all(|item| matches_query(item, entry, today))
+        }
+    }
+}
+
+fn matches_item<T: SearchFields>(item: &Item, entry: &T, today: NaiveDate) -> bool {
+    let hit = match entry.field(&item.data.name) {
+        Some(field) => matches_value(&item.data.value, &field, today),
+        None => false,
+    };
+    hit != item.not
+}
+
+fn matches_value(value: &ItemValue, field: &FieldValue, today: NaiveDate) -> bool {
+    match (value, field) {
+        (ItemValue::None, FieldValue::Bool(b)) => *b,
+        (ItemValue::None, FieldValue::Text(v) | FieldValue::Keyword(v)) => !v.is_empty(),
+        (ItemValue::None, _) => true,
+        (ItemValue::Bool(a), FieldValue::Bool(b)) => a == b,
+        (ItemValue::Int(a), FieldValue::Int(b)) => a == b,
+        (ItemValue::String(s), FieldValue::Text(values)) => {
+            let s = s.to_lowercase();
+            values.iter().any(|v| v.to_lowercase().contains(&s))
+        }
+        (ItemValue::String(s), FieldValue::Keyword(values)) => {
+            values.iter().any(|v| v.eq_ignore_ascii_case(s))
+        }
+        (ItemValue::CmpInt { eq, bigger, value }, field) => cmp(field, *value as f64, *eq, *bigger),
+        (ItemValue::CmpFloat { eq, bigger, value }, field) => {
+            cmp(field, *value as f64, *eq, *bigger)
+        }
+        (ItemValue::Date(query), FieldValue::Date(date)) => query.matches(*date, today),
+        (ItemValue::Set(values), field) => values.iter().any(|v| matches_value(v, field, today)),
+        (
+            ItemValue::Range {
+                min,
+                max,
+                inclusive,
+            },
+            field,
+        ) => match (as_f64(field), value_f64(min), value_f64(max)) {
+            (Some(v), Some(min), Some(max)) => v >= min && (v < max || (*inclusive && v == max)),
+            _ => false,
+        },
+        (ItemValue::Float(a), field) => {
+            as_f64(field).is_some_and(|b| format!("{:.4}", a) == format!("{:.4}", b))
+        }
+        _ => false,
+    }
+}
+
+fn cmp(field: &FieldValue, value: f64, eq: bool, bigger: bool) -> bool {
+    let field = match as_f64(field) {
+        Some(v) => v,
+        None => return false,
+    };
+    match (bigger, eq) {
+        (true, true) => field >= value,
+        (true, false) => field > value,
+        (false, true) => field <= value,
+        (false, false) => field < value,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::search::parser::parse_query;
+    use crate::search::schema::SearchSchema;
+
+    #[derive(Default)]
+    struct Entry {
+        title: &'static str,
+        tags: Vec<&'static str>,
+        chapters: Option<i64>,
+        rating: Option<f64>,
+        favorite: bool,
+        last_read: NaiveDate,
+    }
+
+    impl SearchFields for Entry {
+        fn field(&self, name: &str) -> Option<FieldValue<'_>> {
+            match name {
+                "title" => Some(FieldValue::Text(vec![Cow::Borrowed(self.title)])),
+                "tag" => Some(FieldValue::Keyword(
+                    self.tags.iter().map(|v| Cow::Borrowed(*v)).collect(),
+                )),
+                "chapters" => self.chapters.map(FieldValue::Int),
+                "rating" => self.rating.map(FieldValue::Float),
+                "favorite" => Some(FieldValue::Bool(self.favorite)),
+                "last_read" => Some(FieldValue::Date(self.last_read)),
+                _ => None,
+            }
+        }
+
+        fn sort_value(&self, order: Order) -> Option<FieldValue<'_>> {
+            match order {
+                Order::Alphabetical => Some(FieldValue::Text(vec![Cow::Borrowed(self.title)])),
+                Order::Rating => self.rating.map(FieldValue::Float),
+                Order::ChapterCount => self.chapters.map(FieldValue::Int),
+                _ => None,
+            }
+        }
+    }
+
+    fn query(s: &str) -> ItemOrArray {
+        parse_query(s, &SearchSchema::internal().fields).unwrap()
+    }
+
+    fn matches(s: &str, entry: &Entry) -> bool {
+        Matcher::new(&query(s), NaiveDate::default()).matches(entry)
+    }
+
+    fn entry() -> Entry {
+        Entry {
+            title: "One Piece",
+            tags: vec!["Action", "Adventure"],
+            chapters: Some(12),
+            rating: Some(4.0),
+            ..Entry::default()
+        }
+    }
+
+    #[test]
+    fn combines_and_or_not() {
+        let entry = entry();
+        assert!(matches("t:piece tag:!gore", &entry));
+        assert!(!matches("t:piece tag:!action", &entry));
+        assert!(matches("or:(t:naruto ch:>=12)", &entry));
+        assert!(!matches("or:(t:naruto ch:>=13)", &entry));
+        assert!(matches("and:(t:one or:(tag:gore r:>=4))", &entry));
+        assert!(matches("and:()", &entry));
+        assert!(!matches("or:()", &entry));
+    }
+
+    #[test]
+    fn compares_numbers() {
+        let entry = entry();
+        assert!(matches("ch:>=12 ch:<=12 ch:>11 ch:<13", &entry));
+        assert!(!matches("ch:>12", &entry));
+        assert!(!matches("ch:<12", &entry));
+        assert!(matches("r:>3.5 r:<=4", &entry));
+        assert!(!matches("r:<4", &entry));
+        assert!(!matches("ch:>1", &Entry::default()));
+    }
+
+    #[test]
+    fn text_contains_and_keywords_equal() {
+        let entry = entry();
+        assert!(matches("t:PIECE", &entry));
+        assert!(matches("t:\"e pi\"", &entry));
+        assert!(matches("tag:ACTION", &entry));
+        assert!(!matches("tag:act", &entry));
+        assert!(!matches("t:naruto", &entry));
+    }
+
+    #[test]
+    fn matches_sets_and_ranges() {
+        let entry = entry();
+        assert!(matches("ch:{1,12}", &entry));
+        assert!(!matches("ch:{1,2}", &entry));
+        assert!(matches("tag:{gore,adventure}", &entry));
+        assert!(!matches("tag:{}", &entry));
+        assert!(matches("r:3..=4 ch:12..13", &entry));
+        assert!(!matches("r:3..4", &entry));
+        assert!(!matches("ch:1..12", &entry));
+        assert!(!matches("r:3..=4", &Entry::default()));
+    }
+
+    #[test]
+    fn sorts_by_keys_with_missing_values_last() {
+        let entries = [
+            Entry {
+                title: "b",
+                rating: Some(4.0),
+                ..Entry::default()
+            },
+            Entry {
+                title: "c",
+                ..Entry::default()
+            },
+            Entry {
+                title: "a",
+                rating: Some(4.0),
+                ..Entry::default()
+            },
+            Entry {
+                title: "d",
+                rating: Some(5.0),
+                ..Entry::default()
+            },
+        ];
+        let titles = |keys: &[OrderBy]| {
+            let mut refs = entries.iter().collect::<Vec<_>>();
+            sort_by(&mut refs, keys);
+            refs.iter().map(|v| v.title).collect::<Vec<_>>()
+        };
+        let key = |order, desc| OrderBy { order, desc };
+        assert_eq!(titles(&[key(Order::Rating, true)]), ["d", "b", "a", "c"]);
+        assert_eq!(titles(&[key(Order::Rating, false)]), ["b", "a", "d", "c"]);
+        assert_eq!(
+            titles(&[key(Order::Rating, true), key(Order::Alphabetical, false)]),
+            ["d", "a", "b", "c"]
+        );
+        assert_eq!(
+            titles(&[key(Order::Rating, false), key(Order::Alphabetical, true)]),
+            ["b", "a", "d", "c"]
+        );
+    }
+
+    #[test]
+    fn searches_pages() {
+        let entries = ["e", "b", "x", "d", "a", "c"]
+            .into_iter()
+            .map(|title| Entry {
+                title,
+                favorite: title != "x",
+                ..Entry::default()
+            })
+            .collect::<Vec<_>>();
+        let search = |limit, page| {
+            let request = SearchRequest {
+                order: Order::Alphabetical,
+                desc: false,
+                then: vec![],
+                seed: None,
+                limit,
+                page,
+                query: query("fav:"),
+                facets: vec![],
+                highlight: false,
+            };
+            Matcher::search(&request, &entries, NaiveDate::default())
+                .iter()
+                .map(|v| v.title)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(search(2, 0), ["a", "b"]);
+        assert_eq!(search(2, 1), ["a", "b"]);
+        assert_eq!(search(2, 2), ["c", "d"]);
+        assert_eq!(search(2, 3), ["e"]);
+        assert!(search(2, 4).is_empty());
+        assert!(search(0, 1).is_empty());
+    }
+
+    #[test]
+    fn resolves_dates_against_today() {
+        let query = query("lr:>=-7d");
+        let entry = Entry {
+            last_read: NaiveDate::from_ymd_opt(2024, 3, 1).unwrap(),
+            ..Entry::default()
+        };
+        let matches = |y, m, d| {
+            Matcher::new(&query, NaiveDate::from_ymd_opt(y, m, d).unwrap()).matches(&entry)
+        };
+        assert!(matches(2024, 3, 8));
+        assert!(!matches(2024, 3, 9));
+    }
+
+    #[test]
+    fn valueless_bools_match_true() {
+        // same query as the compiler test `compiles_valueless_bools_as_true`
+        let query = query("fav: or:(fav:! rating:)");
+        let matches = |favorite, rating| {
+            Matcher::new(&query, NaiveDate::default()).matches(&Entry {
+                favorite,
+                rating,
+                ..Entry::default()
+            })
+        };
+        assert!(matches(true, Some(4.0)));
+        assert!(!matches(true, None));
+        assert!(!matches(false, Some(4.0)));
+    }
+}
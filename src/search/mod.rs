@@ -0,0 +1,748 @@
+use crate::error::ApiErr;
+use crate::pagination::Cursor;
+use crate::search::date::DateQuery;
+use crate::title::TitleSet;
+use crate::ApiErrorType;
+use crate::RequestImpl;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+use std::fmt::{Display, Formatter};
+use std::hash::{Hash, Hasher};
+use std::str::FromStr;
+
+pub mod builder;
+pub mod compile;
+pub mod date;
+pub mod facets;
+pub mod fuzzy;
+pub mod highlight;
+pub mod history;
+pub mod matcher;
+pub mod normalize;
+pub mod parser;
+pub mod saved;
+pub mod schema;
+pub mod suggest;
+#[cfg(test)]
+mod test_util;
+pub mod validate;
+pub mod wire;
+
+pub trait DisplaySearch: DeserializeOwned + Send {
+    fn image_number(&self) -> u32;
+    fn internal(&self) -> bool;
+    fn id_url(&self) -> &String;
+    fn ext(&self) -> Cow<String>;
+    fn status(&self) -> Cow<Status>;
+    fn titles(&self) -> Cow<TitleSet>;
+    fn cover(&self) -> &str;
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+pub struct SearchResponse {
+    pub manga_id: String,
+    pub titles: TitleSet,
+    pub tags: Vec<String>,
+    pub status: Status,
+    pub ext: String,
+    pub number: u32,
+    /// parts which matched the query, only filled if requested
+    #[serde(default)]
+    pub highlights: Vec<highlight::Highlight>,
+}
+
+impl DisplaySearch for SearchResponse {
+    fn image_number(&self) -> u32 {
+        self.number
+    }
+
+    fn internal(&self) -> bool {
+        true
+    }
+
+    fn id_url(&self) -> &String {
+        &self.manga_id
+    }
+
+    fn ext(&self) -> Cow<String> {
+        Cow::Borrowed(&self.ext)
+    }
+
+    fn status(&self) -> Cow<Status> {
+        Cow::Borrowed(&self.status)
+    }
+
+    fn titles(&self) -> Cow<TitleSet> {
+        Cow::Borrowed(&self.titles)
+    }
+
+    fn cover(&self) -> &str {
+        ""
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub enum Status {
+    Dropped,
+    Hiatus,
+    Ongoing,
+    Completed,
+    Upcoming,
+}
+
+impl TryFrom<u64> for Status {
+    type Error = ApiErr;
+
+    fn try_from(value: u64) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(Self::Dropped),
+            1 => Ok(Self::Hiatus),
+            2 => Ok(Self::Ongoing),
+            3 => Ok(Self::Completed),
+            4 => Ok(Self::Upcoming),
+            _ => Err(ApiErr {
+                message: Some("Couldnt find manga status".to_string()),
+                cause: None,
+                err_type: ApiErrorType::InternalError,
+            }),
+        }
+    }
+}
+
+impl Status {
+    pub const ALL: [Status; 5] = [
+        Status::Dropped,
+        Status::Hiatus,
+        Status::Ongoing,
+        Status::Completed,
+        Status::Upcoming,
+    ];
+}
+
+impl Display for Status {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Status::Dropped => write!(f, "dropped"),
+            Status::Hiatus => write!(f, "hiatus"),
+            Status::Ongoing => write!(f, "ongoing"),
+            Status::Completed => write!(f, "completed"),
+            Status::Upcoming => write!(f, "upcoming"),
+        }
+    }
+}
+
+impl FromStr for Status {
+    type Err = ApiErr;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .into_iter()
+            .find(|status| status.to_string().eq_ignore_ascii_case(s))
+            .ok_or_else(|| ApiErr {
+                message: Some("Couldnt find manga status".to_string()),
+                cause: Some(s.to_string()),
+                err_type: ApiErrorType::InvalidInput,
+            })
+    }
+}
+
+impl From<Status> for u64 {
+    fn from(value: Status) -> Self {
+        match value {
+            Status::Dropped => 0,
+            Status::Hiatus => 1,
+            Status::Ongoing => 2,
+            Status::Completed => 3,
+            Status::Upcoming => 4,
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug, PartialEq, Eq)]
+pub struct SearchRequest {
+    pub order: Order,
+    pub desc: bool,
+    /// sort keys used if the previous ones are equal
+    #[serde(default)]
+    pub then: Vec<OrderBy>,
+    /// makes [`Order::Random`] stable between pages
+    #[serde(default)]
+    pub seed: Option<u64>,
+    pub limit: u32,
+    pub page: u32,
+    /// sent as a [`wire::WireQuery`], the untagged form is still accepted
+    #[serde(with = "wire::versioned")]
+    pub query: ItemOrArray,
+    /// fields to count the values of, answered with a [`facets::FacetedSearchResponse`] if not empty
+    #[serde(default)]
+    pub facets: Vec<String>,
+    /// fill [`SearchResponse::highlights`]
+    #[serde(default)]
+    pub highlight: bool,
+}
+
+impl RequestImpl for SearchRequest {
+    const ROUTE: &'static str = "search";
+    const AUTH: bool = true;
+}
+
+/// [`SearchRequest`] which continues after a cursor instead of using a page number
+///
+/// Response: [`crate::pagination::Page<SearchResponse>`]
+#[derive(Deserialize, Serialize, Debug, PartialEq, Eq)]
+pub struct SearchCursorRequest {
+    pub order: Order,
+    pub desc: bool,
+    /// see [`SearchRequest::then`], the cursor holds a key for the order and each of these
+    #[serde(default)]
+    pub then: Vec<OrderBy>,
+    /// see [`SearchRequest::seed`]
+    #[serde(default)]
+    pub seed: Option<u64>,
+    pub limit: u32,
+    /// `None` for the first page
+    pub cursor: Option<Cursor>,
+    #[serde(with = "wire::versioned")]
+    pub query: ItemOrArray,
+    /// see [`SearchRequest::facets`]
+    #[serde(default)]
+    pub facets: Vec<String>,
+    /// see [`SearchRequest::highlight`]
+    #[serde(default)]
+    pub highlight: bool,
+}
+
+impl RequestImpl for SearchCursorRequest {
+    const ROUTE: &'static str = "search/cursor";
+    const AUTH: bool = true;
+}
+
+#[derive(Deserialize, Serialize, Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum Order {
+    Created,
+    Alphabetical,
+    Updated,
+    LastRead,
+    Popularity,
+    /// stable between pages if a seed is given
+    Random,
+    Rating,
+    ChapterCount,
+    /// how well the text items of the query match
+    Relevance,
+    ReleaseDate,
+}
+
+/// sort key with its own direction
+#[derive(Deserialize, Serialize, Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub struct OrderBy {
+    pub order: Order,
+    pub desc: bool,
+}
+
+/// can contain item or array
+#[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq, Hash)]
+#[serde(untagged)]
+pub enum ItemOrArray {
+    Item(Item),
+    Array(Array),
+}
+
+impl Display for ItemOrArray {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                ItemOrArray::Item(v) => v.to_string(),
+                ItemOrArray::Array(v) => v.to_string(),
+            }
+        )
+    }
+}
+
+/// array joined with and or or
+#[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq, Hash)]
+pub struct Array {
+    pub or: bool,
+    pub items: Vec<ItemOrArray>,
+}
+
+impl Display for Array {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let prefix = match self.or {
+            true => "or:(",
+            false => "and:(",
+        };
+        write!(
+            f,
+            "{}",
+            format!(
+                "{prefix}{})",
+                self.items
+                    .iter()
+                    .map(|v| v.to_string())
+                    .collect::<Vec<_>>()
+                    .join(" ")
+            )
+        )
+    }
+}
+
+/// item include or exclude
+#[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq, Hash)]
+pub struct Item {
+    pub not: bool,
+    pub data: ItemData,
+}
+
+impl Display for Item {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            format!(
+                "{}:{}{}",
+                self.data.name,
+                match self.not {
+                    true => "!",
+                    false => "",
+                },
+                self.data.value
+            )
+        )
+    }
+}
+
+impl Item {
+    pub fn new(data: ItemData) -> Self {
+        Self { not: false, data }
+    }
+
+    pub fn new_exclude(data: ItemData) -> Self {
+        Self { not: true, data }
+    }
+}
+
+/// field and value
+#[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq, Hash)]
+pub struct ItemData {
+    pub name: String,
+    pub value: ItemValue,
+}
+
+impl ItemData {
+    pub fn enum_(name: impl ToString) -> Self {
+        Self {
+            name: name.to_string(),
+            value: ItemValue::None,
+        }
+    }
+}
+
+/// define the type it should be parsed to
+#[derive(Serialize, Deserialize, Debug, Copy, Clone, Eq, PartialEq)]
+pub enum ItemKind {
+    Bool,
+    Int,
+    String,
+    CmpFloat,
+    CmpInt,
+    Float,
+    Date,
+}
+
+impl ItemKind {
+    /// kind of the values inside a [`ItemValue::Range`] or [`ItemValue::Set`]
+    pub fn base(&self) -> ItemKind {
+        match self {
+            ItemKind::CmpFloat => ItemKind::Float,
+            ItemKind::CmpInt => ItemKind::Int,
+            kind => *kind,
+        }
+    }
+
+    /// also accepts sets like `{a,b}` and for numbers ranges like `3..4` or `3..=4`
+    pub fn parse(&self, s: &str) -> Result<ItemValue, String> {
+        let err = || format!("Failed to parse: {}", s);
+        if let Some(inner) = s.strip_prefix('{').and_then(|v| v.strip_suffix('}')) {
+            return Ok(ItemValue::Set(
+                inner
+                    .split(',')
+                    .filter(|v| !v.trim().is_empty())
+                    .map(|v| self.base().parse(v.trim()))
+                    .collect::<Result<_, _>>()?,
+            ));
+        }
+        if let (Some((min, max)), ItemKind::Int | ItemKind::Float) =
+            (s.split_once(".."), self.base())
+        {
+            let (inclusive, max) = match max.strip_prefix('=') {
+                Some(max) => (true, max),
+                None => (false, max),
+            };
+            return Ok(ItemValue::Range {
+                min: Box::new(self.base().parse(min)?),
+                max: Box::new(self.base().parse(max)?),
+                inclusive,
+            });
+        }
+        Ok(match self {
+            ItemKind::Bool => ItemValue::Bool(s.parse().map_err(|_| err())?),
+            ItemKind::Int => ItemValue::Int(s.parse().map_err(|_| err())?),
+            ItemKind::Float => ItemValue::Float(s.parse().map_err(|_| err())?),
+            ItemKind::String => ItemValue::String(s.to_string()),
+            ItemKind::CmpFloat => {
+                let (bigger, eq, value) = parse(s)?;
+                ItemValue::CmpFloat { eq, bigger, value }
+            }
+            ItemKind::CmpInt => {
+                let (bigger, eq, value) = parse(s)?;
+                ItemValue::CmpInt { eq, bigger, value }
+            }
+            ItemKind::Date => ItemValue::Date(s.parse()?),
+        })
+    }
+}
+
+/// enum with different values
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub enum ItemValue {
+    None,
+    Bool(bool),
+    Int(i64),
+    Float(f64),
+    String(String),
+    CmpFloat {
+        eq: bool,
+        bigger: bool,
+        value: f32,
+    },
+    CmpInt {
+        eq: bool,
+        bigger: bool,
+        value: i64,
+    },
+    Date(DateQuery),
+    /// `min..max` or `min..=max`
+    Range {
+        min: Box<ItemValue>,
+        max: Box<ItemValue>,
+        inclusive: bool,
+    },
+    /// matches if one of the values matches
+    Set(Vec<ItemValue>),
+}
+
+/// mostly auto generated
+impl PartialEq for ItemValue {
+    #[inline]
+    fn eq(&self, other: &ItemValue) -> bool {
+        let self_tag = core::mem::discriminant(self);
+        let other_tag = core::mem::discriminant(other);
+        self_tag == other_tag
+            && match (self, other) {
+                (ItemValue::Bool(self_value), ItemValue::Bool(other_value)) => {
+                    *self_value == *other_value
+                }
+                (ItemValue::Int(self_value), ItemValue::Int(other_value)) => {
+                    *self_value == *other_value
+                }
+                (ItemValue::Float(self_value), ItemValue::Float(other_value)) => {
+                    format!("{:.4}", self_value) == format!("{:.4}", other_value)
+                }
+                (ItemValue::String(self_value), ItemValue::String(other_value)) => {
+                    *self_value == *other_value
+                }
+                (
+                    ItemValue::CmpInt {
+                        eq: self_eq,
+                        bigger: self_bigger,
+                        value: self_value,
+                    },
+                    ItemValue::CmpInt {
+                        eq: other_eq,
+                        bigger: other_bigger,
+                        value: other_value,
+                    },
+                ) => {
+                    *self_eq == *other_eq
+                        && *self_bigger == *other_bigger
+                        && *self_value == *other_value
+                }
+                (
+                    ItemValue::CmpFloat {
+                        eq: self_eq,
+                        bigger: self_bigger,
+                        value: self_value,
+                    },
+                    ItemValue::CmpFloat {
+                        eq: other_eq,
+                        bigger: other_bigger,
+                        value: other_value,
+                    },
+                ) => {
+                    *self_eq == *other_eq
+                        && *self_bigger == *other_bigger
+                        && format!("{:.4}", self_value) == format!("{:.4}", other_value)
+                }
+                (ItemValue::Date(self_value), ItemValue::Date(other_value)) => {
+                    *self_value == *other_value
+                }
+                (
+                    ItemValue::Range {
+                        min: self_min,
+                        max: self_max,
+                        inclusive: self_inclusive,
+                    },
+                    ItemValue::Range {
+                        min: other_min,
+                        max: other_max,
+                        inclusive: other_inclusive,
+                    },
+                ) => {
+                    *self_inclusive == *other_inclusive
+                        && *self_min == *other_min
+                        && *self_max == *other_max
+                }
+                (ItemValue::Set(self_value), ItemValue::Set(other_value)) => {
+                    *self_value == *other_value
+                }
+                _ => true,
+            }
+    }
+}
+
+impl Eq for ItemValue {}
+
+/// consistent with the [`PartialEq`] impl, floats are hashed with 4 decimals
+impl Hash for ItemValue {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        core::mem::discriminant(self).hash(state);
+        match self {
+            ItemValue::None => {}
+            ItemValue::Bool(v) => v.hash(state),
+            ItemValue::Int(v) => v.hash(state),
+            ItemValue::Float(v) => format!("{:.4}", v).hash(state),
+            ItemValue::String(v) => v.hash(state),
+            ItemValue::CmpFloat { eq, bigger, value } => {
+                eq.hash(state);
+                bigger.hash(state);
+                format!("{:.4}", value).hash(state);
+            }
+            ItemValue::CmpInt { eq, bigger, value } => {
+                eq.hash(state);
+                bigger.hash(state);
+                value.hash(state);
+            }
+            ItemValue::Date(v) => v.hash(state),
+            ItemValue::Range {
+                min,
+                max,
+                inclusive,
+            } => {
+                min.hash(state);
+                max.hash(state);
+                inclusive.hash(state);
+            }
+            ItemValue::Set(v) => v.hash(state),
+        }
+    }
+}
+
+impl ItemValue {
+    /// kind this value is parsed from, `None` for [`ItemValue::None`]
+    ///
+    /// ranges and sets return the kind of their values
+    pub fn kind(&self) -> Option<ItemKind> {
+        match self {
+            ItemValue::None => None,
+            ItemValue::Bool(_) => Some(ItemKind::Bool),
+            ItemValue::Int(_) => Some(ItemKind::Int),
+            ItemValue::Float(_) => Some(ItemKind::Float),
+            ItemValue::String(_) => Some(ItemKind::String),
+            ItemValue::CmpFloat { .. } => Some(ItemKind::CmpFloat),
+            ItemValue::CmpInt { .. } => Some(ItemKind::CmpInt),
+            ItemValue::Date(_) => Some(ItemKind::Date),
+            ItemValue::Range { min, .. } => min.kind(),
+            ItemValue::Set(v) => v.first().and_then(|v| v.kind()),
+        }
+    }
+}
+
+impl Display for ItemValue {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                ItemValue::None => String::new(),
+                ItemValue::Bool(bool) => bool.to_string(),
+                ItemValue::Int(v) => v.to_string(),
+                ItemValue::Float(v) => v.to_string(),
+                ItemValue::String(s) => {
+                    format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\""))
+                }
+                ItemValue::CmpFloat { eq, bigger, value } => {
+                    format!(
+                        "{}{}{}",
+                        match bigger {
+                            true => ">",
+                            false => "<",
+                        },
+                        match eq {
+                            true => "=",
+                            false => "",
+                        },
+                        value
+                    )
+                }
+                ItemValue::CmpInt { eq, bigger, value } => {
+                    format!(
+                        "{}{}{}",
+                        match bigger {
+                            true => ">",
+                            false => "<",
+                        },
+                        match eq {
+                            true => "=",
+                            false => "",
+                        },
+                        value
+                    )
+                }
+                ItemValue::Date(v) => v.to_string(),
+                ItemValue::Range {
+                    min,
+                    max,
+                    inclusive,
+                } => format!(
+                    "{min}..{}{max}",
+                    match inclusive {
+                        true => "=",
+                        false => "",
+                    }
+                ),
+                ItemValue::Set(v) => format!(
+                    "{{{}}}",
+                    v.iter()
+                        .map(|v| v.to_string())
+                        .collect::<Vec<_>>()
+                        .join(",")
+                ),
+            }
+        )
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Field {
+    pub name: String,
+    pub abbr: Vec<String>,
+    pub kind: ItemKind,
+    /// allowed values, empty if every value is allowed
+    #[serde(default)]
+    pub values: Vec<String>,
+    #[serde(default)]
+    pub description: Option<String>,
+}
+
+impl Field {
+    pub fn new(name: String, abbr: Vec<String>, kind: ItemKind) -> Self {
+        Self {
+            name,
+            abbr,
+            kind,
+            values: vec![],
+            description: None,
+        }
+    }
+}
+
+fn parse<T: FromStr>(s: &str) -> Result<(bool, bool, T), String> {
+    let (str, b, s) = if let Some(v) = s.strip_prefix('>') {
+        (v, true, false)
+    } else if let Some(v) = s.strip_prefix('<') {
+        (v, false, true)
+    } else {
+        (s, false, false)
+    };
+    let (eq, num) = if let Some(v) = str.strip_prefix('=') {
+        (true, v)
+    } else {
+        (false, str)
+    };
+    Ok((
+        b == s || b,
+        eq,
+        num.parse::<T>()
+            .map_err(|_| format!("Failed to parse: {}", num))?,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::search::test_util::range;
+
+    #[test]
+    fn parses_ranges_with_the_base_kind() {
+        assert_eq!(
+            ItemKind::CmpFloat.parse("3..4"),
+            Ok(range(ItemValue::Float(3.0), ItemValue::Float(4.0), false))
+        );
+        assert_eq!(
+            ItemKind::CmpInt.parse("-3..=4"),
+            Ok(range(ItemValue::Int(-3), ItemValue::Int(4), true))
+        );
+        assert_eq!(
+            ItemKind::String.parse("3..4"),
+            Ok(ItemValue::String("3..4".to_string()))
+        );
+        assert_eq!(
+            ItemKind::Date.parse("2023..2024").map(|v| v.to_string()),
+            Ok("2023..2024".to_string())
+        );
+        assert_eq!(
+            ItemKind::Int.parse("3..x"),
+            Err("Failed to parse: x".to_string())
+        );
+        assert!(ItemKind::Int.parse("3..>4").is_err());
+    }
+
+    #[test]
+    fn parses_sets_with_the_base_kind() {
+        assert_eq!(
+            ItemKind::CmpInt.parse("{1, 2,}"),
+            Ok(ItemValue::Set(vec![ItemValue::Int(1), ItemValue::Int(2)]))
+        );
+        assert_eq!(
+            ItemKind::String.parse("{ongoing,hiatus}"),
+            Ok(ItemValue::Set(vec![
+                ItemValue::String("ongoing".to_string()),
+                ItemValue::String("hiatus".to_string()),
+            ]))
+        );
+        assert_eq!(ItemKind::Bool.parse("{}"), Ok(ItemValue::Set(vec![])));
+        assert!(ItemKind::Bool.parse("{true,1}").is_err());
+    }
+
+    #[test]
+    fn compares_ranges_and_sets() {
+        let a = range(ItemValue::Float(3.0), ItemValue::Float(4.0), false);
+        assert_eq!(
+            a,
+            range(ItemValue::Float(3.00001), ItemValue::Float(4.0), false)
+        );
+        assert_ne!(a, range(ItemValue::Float(3.0), ItemValue::Float(4.0), true));
+        assert_ne!(a, range(ItemValue::Int(3), ItemValue::Int(4), false));
+        assert_ne!(
+            ItemValue::Set(vec![ItemValue::Int(1), ItemValue::Int(2)]),
+            ItemValue::Set(vec![ItemValue::Int(2), ItemValue::Int(1)])
+        );
+        assert_eq!(a.to_string(), "3..4");
+        assert_eq!(a.kind(), Some(ItemKind::Float));
+        assert_eq!(ItemValue::Set(vec![]).kind(), None);
+    }
+}
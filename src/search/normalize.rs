@@ -0,0 +1,152 @@
+use crate::search::{Array, Item, ItemOrArray, ItemValue};
+
+impl ItemOrArray {
+    /// brings the query into a canonical form, so equivalent queries compare and hash equal
+    ///
+    /// - nested arrays with the same operator get flattened
+    /// - duplicates get removed and items are sorted by their text representation
+    /// - arrays with a single item are replaced by the item
+    /// - values of sets get sorted and deduplicated
+    /// - contradictions get replaced by an empty `or:()` which never matches,
+    ///   tautologies by an empty `and:()` which always matches
+    pub fn normalize(self) -> Self {
+        match self {
+            ItemOrArray::Item(item) => ItemOrArray::Item(item.normalize()),
+            ItemOrArray::Array(array) => array.normalize(),
+        }
+    }
+
+    /// `or:()`
+    pub fn is_always_false(&self) -> bool {
+        matches!(self, ItemOrArray::Array(Array { or: true, items }) if items.is_empty())
+    }
+
+    /// `and:()`
+    pub fn is_always_true(&self) -> bool {
+        matches!(self, ItemOrArray::Array(Array { or: false, items }) if items.is_empty())
+    }
+}
+
+impl Item {
+    fn normalize(mut self) -> Self {
+        if let ItemValue::Set(values) = &mut self.data.value {
+            values.sort_by_cached_key(|value| value.to_string());
+            values.dedup();
+        }
+        self
+    }
+}
+
+impl Array {
+    fn normalize(self) -> ItemOrArray {
+        let or = self.or;
+        let mut items = vec![];
+        for item in self.items {
+            match item.normalize() {
+                // also drops neutral elements, because they are empty arrays of the same kind
+                ItemOrArray::Array(child) if child.or == or => items.extend(child.items),
+                child => items.push(child),
+            }
+        }
+        let absorbing = ItemOrArray::Array(Array {
+            or: !or,
+            items: vec![],
+        });
+        if items.contains(&absorbing) {
+            return absorbing;
+        }
+        items.sort_by_cached_key(|item| item.to_string());
+        items.dedup();
+        if contains_negated_pair(&items) {
+            return absorbing;
+        }
+        match items.len() {
+            1 => items.remove(0),
+            _ => ItemOrArray::Array(Array { or, items }),
+        }
+    }
+}
+
+/// `x` and `!x` can never both be true in an and, and one of them is always true in an or
+fn contains_negated_pair(items: &[ItemOrArray]) -> bool {
+    items.iter().enumerate().any(|(i, a)| {
+        items[i + 1..].iter().any(|b| match (a, b) {
+            (ItemOrArray::Item(a), ItemOrArray::Item(b)) => a.not != b.not && a.data == b.data,
+            _ => false,
+        })
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::search::parser::parse_query;
+    use crate::search::schema::SearchSchema;
+    use crate::search::ItemOrArray;
+    use std::collections::hash_map::DefaultHasher;
+    use std::hash::{Hash, Hasher};
+
+    fn normalize(s: &str) -> String {
+        parse_query(s, &SearchSchema::internal().fields)
+            .unwrap()
+            .normalize()
+            .to_string()
+    }
+
+    fn hash(query: &ItemOrArray) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        query.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    #[test]
+    fn flattens_sorts_and_dedups() {
+        assert_eq!(
+            normalize("and:(t:b and:(t:a t:b) or:(t:d or:(t:c)))"),
+            normalize("and:(t:a t:b or:(t:c t:d))")
+        );
+        assert_eq!(normalize("and:(t:a t:a)"), normalize("t:a"));
+        assert_eq!(normalize("or:(and:(t:a))"), normalize("t:a"));
+        assert_eq!(normalize("and:(t:a and:())"), normalize("t:a"));
+        assert_eq!(normalize("or:(t:a or:())"), normalize("t:a"));
+    }
+
+    #[test]
+    fn replaces_absorbing_arrays() {
+        assert_eq!(normalize("and:(t:a or:())"), "or:()");
+        assert_eq!(normalize("or:(t:a and:())"), "and:()");
+        assert_eq!(
+            normalize("and:(t:a or:(t:b or:()))"),
+            normalize("and:(t:a t:b)")
+        );
+    }
+
+    #[test]
+    fn replaces_negated_pairs() {
+        assert_eq!(normalize("and:(t:a t:b t:!a)"), "or:()");
+        assert_eq!(normalize("or:(t:a t:b t:!a)"), "and:()");
+        assert_eq!(normalize("and:(t:a or:(t:b t:!b))"), normalize("t:a"));
+        assert_eq!(normalize("and:(t:a t:!b)"), normalize("and:(t:!b t:a)"));
+    }
+
+    #[test]
+    fn sorts_sets() {
+        assert_eq!(
+            normalize("s:{ongoing,hiatus,ongoing}"),
+            normalize("status:{hiatus,ongoing}")
+        );
+    }
+
+    #[test]
+    fn equivalent_queries_hash_equal() {
+        let parse = |s| {
+            parse_query(s, &SearchSchema::internal().fields)
+                .unwrap()
+                .normalize()
+        };
+        let a = parse("and:(fav: or:(r:>=4 ch:>10) s:{ongoing,hiatus})");
+        let b = parse("and:(s:{hiatus,ongoing} and:(or:(ch:>10 r:>=4 r:>=4)) favorite:)");
+        assert_eq!(a, b);
+        assert_eq!(hash(&a), hash(&b));
+        assert_ne!(hash(&a), hash(&parse("fav:")));
+    }
+}
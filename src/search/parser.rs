@@ -0,0 +1,724 @@
+use crate::error::{ApiErr, ApiErrorType};
+use crate::search::{Array, Field, Item, ItemData, ItemKind, ItemOrArray, ItemValue};
+use serde::{Deserialize, Serialize};
+use std::fmt::{Display, Formatter};
+use std::ops::Range;
+
+/// parses the text representation produced by [`ItemOrArray`]'s `Display` impl
+///
+/// field names and abbreviations are resolved against `fields` and the value is parsed
+/// with the [`ItemKind`] of the field. multiple top level items are joined with and.
+pub fn parse_query(s: &str, fields: &[Field]) -> Result<ItemOrArray, QueryParseError> {
+    parse(s, fields, None)
+}
+
+/// [`parse_query`] which accepts free text, bare words and quoted strings without a field
+/// become string items of the field `text`
+pub fn parse_query_with_text(
+    s: &str,
+    fields: &[Field],
+    text: &str,
+) -> Result<ItemOrArray, QueryParseError> {
+    parse(s, fields, Some(text))
+}
+
+fn parse(s: &str, fields: &[Field], text: Option<&str>) -> Result<ItemOrArray, QueryParseError> {
+    let mut parser = Parser {
+        src: s,
+        pos: 0,
+        fields,
+        text,
+    };
+    let mut items = parser.parse_list(None)?;
+    if items.len() == 1 {
+        Ok(items.remove(0))
+    } else {
+        Ok(ItemOrArray::Array(Array { or: false, items }))
+    }
+}
+
+/// finds a field by its name or one of its abbreviations
+pub fn find_field<'a>(fields: &'a [Field], name: &str) -> Option<&'a Field> {
+    fields.iter().find(|field| {
+        field.name.eq_ignore_ascii_case(name)
+            || field
+                .abbr
+                .iter()
+                .any(|abbr| abbr.eq_ignore_ascii_case(name))
+    })
+}
+
+/// returns the candidate with the smallest edit distance if it is close enough to be a typo
+pub fn closest_match<'a>(
+    word: &str,
+    candidates: impl IntoIterator<Item = &'a str>,
+) -> Option<&'a str> {
+    let word = word.to_lowercase();
+    let max = word.chars().count().div_ceil(3).max(1);
+    candidates
+        .into_iter()
+        .map(|candidate| (edit_distance(&word, &candidate.to_lowercase()), candidate))
+        .filter(|(distance, _)| *distance <= max)
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, candidate)| candidate)
+}
+
+/// levenshtein distance
+pub fn edit_distance(a: &str, b: &str) -> usize {
+    let b = b.chars().collect::<Vec<_>>();
+    let mut row = (0..=b.len()).collect::<Vec<_>>();
+    for (i, ca) in a.chars().enumerate() {
+        let mut prev = row[0];
+        row[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let cur = row[j + 1];
+            row[j + 1] = (prev + usize::from(ca != *cb)).min(row[j] + 1).min(cur + 1);
+            prev = cur;
+        }
+    }
+    row[b.len()]
+}
+
+/// error with the byte span of the offending part of the query
+#[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq)]
+pub struct QueryParseError {
+    pub kind: QueryParseErrorKind,
+    pub span: Range<usize>,
+    pub token: String,
+    pub expected: Vec<Expected>,
+    pub suggestion: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq)]
+pub enum QueryParseErrorKind {
+    ExpectedField,
+    UnknownField,
+    UnknownOperator,
+    InvalidValue(String),
+    UnterminatedString,
+    UnterminatedSet,
+    MissingClosingBracket,
+    UnexpectedClosingBracket,
+}
+
+/// what would have been valid at the position of the error
+#[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq)]
+pub enum Expected {
+    Field,
+    Colon,
+    Operator,
+    Value(ItemKind),
+    Quote,
+    Comma,
+    ClosingBrace,
+    ClosingBracket,
+    End,
+}
+
+impl Display for QueryParseError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match &self.kind {
+            QueryParseErrorKind::ExpectedField => write!(f, "Expected field name followed by ':'"),
+            QueryParseErrorKind::UnknownField => write!(f, "Unknown field \"{}\"", self.token),
+            QueryParseErrorKind::UnknownOperator => {
+                write!(f, "Unknown array operator \"{}\"", self.token)
+            }
+            QueryParseErrorKind::InvalidValue(reason) => {
+                write!(f, "Invalid value \"{}\": {reason}", self.token)
+            }
+            QueryParseErrorKind::UnterminatedString => write!(f, "Unterminated string"),
+            QueryParseErrorKind::UnterminatedSet => write!(f, "Expected ',' or '}}' in set"),
+            QueryParseErrorKind::MissingClosingBracket => write!(f, "Missing closing bracket"),
+            QueryParseErrorKind::UnexpectedClosingBracket => {
+                write!(f, "Unexpected closing bracket")
+            }
+        }?;
+        write!(f, " at {}..{}", self.span.start, self.span.end)?;
+        if let Some(suggestion) = &self.suggestion {
+            write!(f, ", did you mean \"{suggestion}\"?")?;
+        }
+        Ok(())
+    }
+}
+
+impl From<QueryParseError> for ApiErr {
+    fn from(value: QueryParseError) -> Self {
+        ApiErr {
+            message: Some(value.to_string()),
+            cause: Some(value.token),
+            err_type: ApiErrorType::InvalidInput,
+        }
+    }
+}
+
+enum RawValue {
+    Single(String, bool, Range<usize>),
+    Set(Vec<(String, bool, Range<usize>)>),
+}
+
+struct Parser<'a> {
+    src: &'a str,
+    pos: usize,
+    fields: &'a [Field],
+    /// field of free text, `None` if every item needs a field
+    text: Option<&'a str>,
+}
+
+impl<'a> Parser<'a> {
+    fn rest(&self) -> &'a str {
+        &self.src[self.pos..]
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.rest().chars().next()
+    }
+
+    fn bump(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.pos += c.len_utf8();
+        Some(c)
+    }
+
+    fn skip_whitespace(&mut self) {
+        while matches!(self.peek(), Some(c) if c.is_whitespace()) {
+            self.bump();
+        }
+    }
+
+    fn error(
+        &self,
+        kind: QueryParseErrorKind,
+        span: Range<usize>,
+        expected: Vec<Expected>,
+    ) -> QueryParseError {
+        QueryParseError {
+            kind,
+            token: self.src[span.clone()].to_string(),
+            span,
+            expected,
+            suggestion: None,
+        }
+    }
+
+    /// parses items until the end of the input or until the closing bracket of the array
+    /// opened at `opened`
+    fn parse_list(&mut self, opened: Option<usize>) -> Result<Vec<ItemOrArray>, QueryParseError> {
+        let mut items = vec![];
+        loop {
+            self.skip_whitespace();
+            match (self.peek(), opened) {
+                (None, Some(start)) => {
+                    return Err(self.error(
+                        QueryParseErrorKind::MissingClosingBracket,
+                        start..self.pos,
+                        vec![Expected::Field, Expected::ClosingBracket],
+                    ));
+                }
+                (None, None) => return Ok(items),
+                (Some(')'), Some(_)) => {
+                    self.bump();
+                    return Ok(items);
+                }
+                (Some(')'), None) => {
+                    return Err(self.error(
+                        QueryParseErrorKind::UnexpectedClosingBracket,
+                        self.pos..self.pos + 1,
+                        vec![Expected::Field, Expected::End],
+                    ))
+                }
+                (Some(_), _) => items.push(self.parse_node()?),
+            }
+        }
+    }
+
+    fn parse_node(&mut self) -> Result<ItemOrArray, QueryParseError> {
+        let start = self.pos;
+        let name = self.take_while(|c| !c.is_whitespace() && !matches!(c, ':' | '(' | ')' | '"'));
+        if let Some(text) = self.text.filter(|_| self.peek() != Some(':')) {
+            let (value, quoted) = match name.is_empty() && self.peek() == Some('"') {
+                true => self.parse_value(false)?,
+                false => (name.to_string(), false),
+            };
+            if quoted || !value.is_empty() {
+                return Ok(ItemOrArray::Item(Item {
+                    not: false,
+                    data: ItemData {
+                        name: text.to_string(),
+                        value: ItemValue::String(value),
+                    },
+                }));
+            }
+        }
+        if name.is_empty() {
+            let end = self.pos + self.peek().map(char::len_utf8).unwrap_or_default();
+            return Err(self.error(
+                QueryParseErrorKind::ExpectedField,
+                start..end,
+                vec![Expected::Field],
+            ));
+        }
+        if self.peek() != Some(':') {
+            let mut err = self.error(
+                QueryParseErrorKind::ExpectedField,
+                start..self.pos,
+                vec![Expected::Colon],
+            );
+            err.suggestion = self.suggest_field(name);
+            return Err(err);
+        }
+        self.bump();
+        if self.peek() == Some('(') {
+            let or = match name {
+                "and" => false,
+                "or" => true,
+                _ => {
+                    let mut err = self.error(
+                        QueryParseErrorKind::UnknownOperator,
+                        start..start + name.len(),
+                        vec![Expected::Operator],
+                    );
+                    err.suggestion = closest_match(name, ["and", "or"]).map(ToString::to_string);
+                    return Err(err);
+                }
+            };
+            self.bump();
+            let items = self.parse_list(Some(start))?;
+            return Ok(ItemOrArray::Array(Array { or, items }));
+        }
+        let not = if self.peek() == Some('!') {
+            self.bump();
+            true
+        } else {
+            false
+        };
+        let value_start = self.pos;
+        let raw = match self.peek() {
+            Some('{') => RawValue::Set(self.parse_set()?),
+            _ => {
+                let (raw, quoted) = self.parse_value(false)?;
+                RawValue::Single(raw, quoted, value_start..self.pos)
+            }
+        };
+        if matches!(&raw, RawValue::Single(raw, false, _) if raw.is_empty()) {
+            // names which aren't fields are allowed values like `ongoing` and stay as typed
+            let name = match find_field(self.fields, name) {
+                Some(field) => field.name.clone(),
+                None => name.to_string(),
+            };
+            return Ok(ItemOrArray::Item(Item {
+                not,
+                data: ItemData {
+                    name,
+                    value: ItemValue::None,
+                },
+            }));
+        }
+        let field = match find_field(self.fields, name) {
+            Some(field) => field,
+            None => {
+                let mut err = self.error(
+                    QueryParseErrorKind::UnknownField,
+                    start..start + name.len(),
+                    vec![Expected::Field],
+                );
+                err.suggestion = self.suggest_field(name);
+                return Err(err);
+            }
+        };
+        let value = match raw {
+            RawValue::Single(raw, quoted, span) => self.value(field.kind, raw, quoted, span)?,
+            RawValue::Set(values) => ItemValue::Set(
+                values
+                    .into_iter()
+                    .map(|(raw, quoted, span)| self.value(field.kind.base(), raw, quoted, span))
+                    .collect::<Result<_, _>>()?,
+            ),
+        };
+        Ok(ItemOrArray::Item(Item {
+            not,
+            data: ItemData {
+                name: field.name.clone(),
+                value,
+            },
+        }))
+    }
+
+    /// nearest field name or abbreviation
+    fn suggest_field(&self, name: &str) -> Option<String> {
+        closest_match(
+            name,
+            self.fields.iter().flat_map(|field| {
+                std::iter::once(field.name.as_str()).chain(field.abbr.iter().map(String::as_str))
+            }),
+        )
+        .map(ToString::to_string)
+    }
+
+    /// quoted strings are always strings, everything else is parsed with the kind
+    fn value(
+        &self,
+        kind: ItemKind,
+        raw: String,
+        quoted: bool,
+        span: Range<usize>,
+    ) -> Result<ItemValue, QueryParseError> {
+        if quoted && kind == ItemKind::String {
+            return Ok(ItemValue::String(raw));
+        }
+        kind.parse(&raw).map_err(|reason| {
+            self.error(
+                QueryParseErrorKind::InvalidValue(reason),
+                span,
+                vec![Expected::Value(kind)],
+            )
+        })
+    }
+
+    /// `{a, "b c", d}`
+    fn parse_set(&mut self) -> Result<Vec<(String, bool, Range<usize>)>, QueryParseError> {
+        let start = self.pos;
+        self.bump();
+        let mut values = vec![];
+        loop {
+            self.skip_whitespace();
+            match self.peek() {
+                Some('}') => {
+                    self.bump();
+                    return Ok(values);
+                }
+                None => {
+                    return Err(self.error(
+                        QueryParseErrorKind::UnterminatedSet,
+                        start..self.pos,
+                        vec![Expected::Comma, Expected::ClosingBrace],
+                    ))
+                }
+                _ => {}
+            }
+            let value_start = self.pos;
+            let (raw, quoted) = self.parse_value(true)?;
+            values.push((raw, quoted, value_start..self.pos));
+            self.skip_whitespace();
+            match self.peek() {
+                Some(',') => {
+                    self.bump();
+                }
+                Some('}') => {}
+                _ => {
+                    let end = self.pos + self.peek().map(char::len_utf8).unwrap_or_default();
+                    return Err(self.error(
+                        QueryParseErrorKind::UnterminatedSet,
+                        start..end,
+                        vec![Expected::Comma, Expected::ClosingBrace],
+                    ));
+                }
+            }
+        }
+    }
+
+    /// quoted strings get unescaped, everything else is read until whitespace or a closing bracket
+    fn parse_value(&mut self, in_set: bool) -> Result<(String, bool), QueryParseError> {
+        if self.peek() != Some('"') {
+            let raw = self.take_while(|c| {
+                !c.is_whitespace() && c != ')' && !(in_set && matches!(c, ',' | '}'))
+            });
+            return Ok((raw.to_string(), false));
+        }
+        let start = self.pos;
+        self.bump();
+        let mut out = String::new();
+        loop {
+            match self.bump() {
+                Some('"') => return Ok((out, true)),
+                Some('\\') if self.peek().is_some() => out.push(self.bump().unwrap_or_default()),
+                Some(c) => out.push(c),
+                None => {
+                    return Err(self.error(
+                        QueryParseErrorKind::UnterminatedString,
+                        start..self.pos,
+                        vec![Expected::Quote],
+                    ))
+                }
+            }
+        }
+    }
+
+    fn take_while(&mut self, f: impl Fn(char) -> bool) -> &'a str {
+        let start = self.pos;
+        while matches!(self.peek(), Some(c) if f(c)) {
+            self.bump();
+        }
+        &self.src[start..self.pos]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::search::schema::SearchSchema;
+    use crate::search::test_util::{array, item, range, strings};
+
+    fn fields() -> Vec<Field> {
+        SearchSchema::internal().fields
+    }
+
+    fn round_trip(query: ItemOrArray) {
+        let text = query.to_string();
+        assert_eq!(parse_query(&text, &fields()), Ok(query), "{text}");
+    }
+
+    #[test]
+    fn round_trips_display_output() {
+        round_trip(item(false, "title", ItemValue::String("x".to_string())));
+        round_trip(item(
+            false,
+            "title",
+            ItemValue::String(r#"say "hi" \ bye"#.to_string()),
+        ));
+        round_trip(item(
+            true,
+            "status",
+            ItemValue::String("ongoing".to_string()),
+        ));
+        round_trip(item(false, "favorite", ItemValue::Bool(true)));
+        round_trip(item(false, "ongoing", ItemValue::None));
+        round_trip(item(true, "favorite", ItemValue::None));
+        round_trip(array(false, vec![]));
+        round_trip(array(true, vec![]));
+        round_trip(array(
+            false,
+            vec![
+                item(false, "title", ItemValue::String("x".to_string())),
+                item(true, "status", ItemValue::String("ongoing".to_string())),
+                item(
+                    false,
+                    "rating",
+                    ItemValue::CmpFloat {
+                        eq: true,
+                        bigger: true,
+                        value: 4.5,
+                    },
+                ),
+                array(
+                    true,
+                    vec![
+                        item(
+                            false,
+                            "chapters",
+                            ItemValue::CmpInt {
+                                eq: false,
+                                bigger: false,
+                                value: 10,
+                            },
+                        ),
+                        item(
+                            true,
+                            "chapters",
+                            ItemValue::CmpInt {
+                                eq: false,
+                                bigger: true,
+                                value: -3,
+                            },
+                        ),
+                        array(false, vec![array(true, vec![])]),
+                    ],
+                ),
+            ],
+        ));
+    }
+
+    #[test]
+    fn resolves_abbreviations() {
+        assert_eq!(
+            parse_query("s:ongoing t:\"a b\"", &fields()),
+            Ok(array(
+                false,
+                vec![
+                    item(false, "status", ItemValue::String("ongoing".to_string())),
+                    item(false, "title", ItemValue::String("a b".to_string())),
+                ]
+            ))
+        );
+        assert_eq!(
+            parse_query("fav: S:! ongoing:", &fields()),
+            Ok(array(
+                false,
+                vec![
+                    item(false, "favorite", ItemValue::None),
+                    item(true, "status", ItemValue::None),
+                    item(false, "ongoing", ItemValue::None),
+                ]
+            ))
+        );
+    }
+
+    #[test]
+    fn parses_ranges_and_sets() {
+        let parse = |s: &str| parse_query(s, &fields());
+        assert_eq!(
+            parse("rating:3..4"),
+            Ok(item(
+                false,
+                "rating",
+                range(ItemValue::Float(3.0), ItemValue::Float(4.0), false)
+            ))
+        );
+        assert_eq!(
+            parse("ch:!3..=4"),
+            Ok(item(
+                true,
+                "chapters",
+                range(ItemValue::Int(3), ItemValue::Int(4), true)
+            ))
+        );
+        assert_eq!(
+            parse("status:{ongoing,hiatus}"),
+            Ok(item(false, "status", strings(&["ongoing", "hiatus"])))
+        );
+        assert_eq!(
+            parse(r#"tag:{ "a, b" , c,"d}\"" }"#),
+            Ok(item(false, "tag", strings(&["a, b", "c", "d}\""])))
+        );
+        assert_eq!(parse("tag:{}"), Ok(item(false, "tag", strings(&[]))));
+        assert_eq!(
+            parse("rating:{1,2.5}"),
+            Ok(item(
+                false,
+                "rating",
+                ItemValue::Set(vec![ItemValue::Float(1.0), ItemValue::Float(2.5)])
+            ))
+        );
+        round_trip(item(
+            false,
+            "rating",
+            range(ItemValue::Float(3.5), ItemValue::Float(4.0), true),
+        ));
+        round_trip(item(true, "status", strings(&["ongoing", "a \"b\", c"])));
+        round_trip(item(
+            false,
+            "chapters",
+            ItemValue::Set(vec![ItemValue::Int(1), ItemValue::Int(-2)]),
+        ));
+    }
+
+    #[test]
+    fn reports_set_errors() {
+        let err = error("tag:{a,b");
+        assert_eq!(err.kind, QueryParseErrorKind::UnterminatedSet);
+        assert_eq!((err.span, err.token.as_str()), (4..8, "{a,b"));
+        assert_eq!(err.expected, vec![Expected::Comma, Expected::ClosingBrace]);
+
+        let err = error("tag:{a b}");
+        assert_eq!(err.kind, QueryParseErrorKind::UnterminatedSet);
+        assert_eq!((err.span, err.token.as_str()), (4..8, "{a b"));
+
+        let err = error("tag:{");
+        assert_eq!(
+            (err.kind, err.span),
+            (QueryParseErrorKind::UnterminatedSet, 4..5)
+        );
+
+        let err = error("rating:{1,x}");
+        assert_eq!(
+            (err.kind, err.span, err.expected),
+            (
+                QueryParseErrorKind::InvalidValue("Failed to parse: x".to_string()),
+                10..11,
+                vec![Expected::Value(ItemKind::Float)]
+            )
+        );
+
+        let err = error("rating:1..x");
+        assert_eq!((err.span, err.token.as_str()), (7..11, "1..x"));
+    }
+
+    fn error(query: &str) -> QueryParseError {
+        parse_query(query, &fields()).unwrap_err()
+    }
+
+    #[test]
+    fn reports_error_spans() {
+        let err = error("title:\"x\" titl:\"y\"");
+        assert_eq!(err.kind, QueryParseErrorKind::UnknownField);
+        assert_eq!((err.span, err.token.as_str()), (10..14, "titl"));
+        assert_eq!(err.suggestion.as_deref(), Some("title"));
+
+        let err = error("rating:>=abc");
+        assert_eq!(
+            err.kind,
+            QueryParseErrorKind::InvalidValue("Failed to parse: abc".to_string())
+        );
+        assert_eq!((err.span, err.token.as_str()), (7..12, ">=abc"));
+        assert_eq!(err.expected, vec![Expected::Value(ItemKind::CmpFloat)]);
+
+        let err = error("title:\"open");
+        assert_eq!(err.kind, QueryParseErrorKind::UnterminatedString);
+        assert_eq!(err.span, 6..11);
+
+        let err = error("and:(title:x");
+        assert_eq!(err.kind, QueryParseErrorKind::MissingClosingBracket);
+        assert_eq!(err.span, 0..12);
+
+        let err = error("title:x )");
+        assert_eq!(err.kind, QueryParseErrorKind::UnexpectedClosingBracket);
+        assert_eq!(err.span, 8..9);
+
+        let err = error("nor:(title:x)");
+        assert_eq!(err.kind, QueryParseErrorKind::UnknownOperator);
+        assert_eq!((err.span, err.suggestion.as_deref()), (0..3, Some("or")));
+
+        let err = error("titel \"x\"");
+        assert_eq!(err.kind, QueryParseErrorKind::ExpectedField);
+        assert_eq!(err.expected, vec![Expected::Colon]);
+        assert_eq!((err.span, err.suggestion.as_deref()), (0..5, Some("title")));
+
+        let err = error("one piece");
+        assert_eq!((err.span, err.suggestion), (0..3, None));
+
+        let err = error("title:x :y");
+        assert_eq!(err.kind, QueryParseErrorKind::ExpectedField);
+        assert_eq!(err.span, 8..9);
+    }
+
+    #[test]
+    fn parses_free_text() {
+        let parse = |s: &str| parse_query_with_text(s, &fields(), "title");
+        let text = |s: &str| item(false, "title", ItemValue::String(s.to_string()));
+        assert_eq!(
+            parse(r#"one piece s:ongoing "a:b (c)""#),
+            Ok(array(
+                false,
+                vec![
+                    text("one"),
+                    text("piece"),
+                    item(false, "status", ItemValue::String("ongoing".to_string())),
+                    text("a:b (c)"),
+                ]
+            ))
+        );
+        assert_eq!(
+            parse("and:(x or:(y z))"),
+            Ok(array(
+                false,
+                vec![text("x"), array(true, vec![text("y"), text("z")])]
+            ))
+        );
+        let err = parse("one titl:x").unwrap_err();
+        assert_eq!(err.kind, QueryParseErrorKind::UnknownField);
+        assert_eq!(err.span, 4..8);
+        let err = parse(r#"one "pi"#).unwrap_err();
+        assert_eq!(err.kind, QueryParseErrorKind::UnterminatedString);
+        assert_eq!(err.span, 4..7);
+    }
+
+    #[test]
+    fn converts_to_invalid_input() {
+        let err = ApiErr::from(error("titl:x"));
+        assert!(matches!(err.err_type, ApiErrorType::InvalidInput));
+        assert_eq!(err.cause.as_deref(), Some("titl"));
+        assert_eq!(
+            err.message.as_deref(),
+            Some("Unknown field \"titl\" at 0..4, did you mean \"title\"?")
+        );
+    }
+}
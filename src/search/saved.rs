@@ -0,0 +1,100 @@
+use crate::search::{wire, ItemOrArray, Order, SearchRequest, SearchResponse};
+use crate::RequestImpl;
+use serde::{Deserialize, Serialize};
+
+/// named search stored for the user
+#[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq)]
+pub struct SavedSearch {
+    pub id: String,
+    pub name: String,
+    #[serde(with = "wire::versioned")]
+    pub query: ItemOrArray,
+    pub order: Order,
+    pub desc: bool,
+    /// shown in the [`crate::home::HomeResponse`]
+    pub pinned: bool,
+}
+
+impl SavedSearch {
+    pub fn to_request(&self, limit: u32, page: u32) -> SearchRequest {
+        SearchRequest {
+            order: self.order,
+            desc: self.desc,
+            then: vec![],
+            seed: None,
+            limit,
+            page,
+            query: self.query.clone(),
+            facets: vec![],
+            highlight: false,
+        }
+    }
+}
+
+/// Response: [`SavedSearch`]
+#[derive(Serialize, Deserialize, Debug)]
+pub struct CreateSavedSearchRequest {
+    pub name: String,
+    #[serde(with = "wire::versioned")]
+    pub query: ItemOrArray,
+    pub order: Order,
+    pub desc: bool,
+    pub pinned: bool,
+}
+
+impl RequestImpl for CreateSavedSearchRequest {
+    const ROUTE: &'static str = "search/saved/create";
+    const AUTH: bool = true;
+}
+
+/// replaces the saved search with the same id
+#[derive(Serialize, Deserialize, Debug)]
+pub struct UpdateSavedSearchRequest {
+    pub search: SavedSearch,
+}
+
+impl RequestImpl for UpdateSavedSearchRequest {
+    const ROUTE: &'static str = "search/saved/update";
+    const AUTH: bool = true;
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct DeleteSavedSearchRequest {
+    pub id: String,
+}
+
+impl RequestImpl for DeleteSavedSearchRequest {
+    const ROUTE: &'static str = "search/saved/delete";
+    const AUTH: bool = true;
+}
+
+/// Response
+#[derive(Serialize, Deserialize, Debug)]
+pub struct SavedSearches {
+    pub searches: Vec<SavedSearch>,
+}
+
+impl RequestImpl for SavedSearches {
+    const ROUTE: &'static str = "search/saved";
+    const AUTH: bool = true;
+}
+
+/// Response: [`Vec<SearchResponse>`]
+#[derive(Serialize, Deserialize, Debug)]
+pub struct RunSavedSearchRequest {
+    pub id: String,
+    pub limit: u32,
+    pub page: u32,
+}
+
+impl RequestImpl for RunSavedSearchRequest {
+    const ROUTE: &'static str = "search/saved/run";
+    const AUTH: bool = true;
+}
+
+/// pinned saved search with its first results
+#[derive(Serialize, Deserialize, Debug)]
+pub struct PinnedSearch {
+    pub search: SavedSearch,
+    pub results: Vec<SearchResponse>,
+}
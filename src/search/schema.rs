@@ -0,0 +1,101 @@
+use crate::search::parser::find_field;
+use crate::search::{Field, ItemKind, Status};
+use crate::RequestImpl;
+use serde::{Deserialize, Serialize};
+
+/// every field the internal search understands
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct SearchSchema {
+    pub fields: Vec<Field>,
+    /// how deep arrays can be nested
+    pub max_depth: usize,
+    pub max_limit: u32,
+}
+
+impl RequestImpl for SearchSchema {
+    const ROUTE: &'static str = "search/schema";
+    const AUTH: bool = true;
+}
+
+impl SearchSchema {
+    pub fn internal() -> Self {
+        Self {
+            fields: vec![
+                field(
+                    "title",
+                    &["t"],
+                    ItemKind::String,
+                    "Any title of the manga in any language",
+                ),
+                field(
+                    "description",
+                    &["desc"],
+                    ItemKind::String,
+                    "Description of the manga",
+                ),
+                field("tag", &["tags"], ItemKind::String, "Tag of the manga"),
+                field("author", &["a"], ItemKind::String, "Name of an author"),
+                field("artist", &["ar"], ItemKind::String, "Name of an artist"),
+                field(
+                    "uploader",
+                    &["u"],
+                    ItemKind::String,
+                    "Name of the user who uploaded the manga",
+                ),
+                Field {
+                    values: Status::ALL.iter().map(ToString::to_string).collect(),
+                    ..field("status", &["s"], ItemKind::String, "Publication status")
+                },
+                field(
+                    "kind",
+                    &["k", "type"],
+                    ItemKind::String,
+                    "Kind of the manga like manga, manhwa or manhua",
+                ),
+                field("chapters", &["ch"], ItemKind::CmpInt, "Number of chapters"),
+                field("rating", &["r"], ItemKind::CmpFloat, "Average rating"),
+                field(
+                    "favorite",
+                    &["fav"],
+                    ItemKind::Bool,
+                    "Marked as favorite by the user",
+                ),
+                field(
+                    "last_read",
+                    &["lr"],
+                    ItemKind::Date,
+                    "When the user last read a chapter",
+                ),
+                field("added", &[], ItemKind::Date, "When the manga was added"),
+                field(
+                    "updated",
+                    &[],
+                    ItemKind::Date,
+                    "When the manga was last updated",
+                ),
+                field(
+                    "released",
+                    &["release"],
+                    ItemKind::Date,
+                    "Release date of the newest chapter",
+                ),
+            ],
+            max_depth: 5,
+            max_limit: 100,
+        }
+    }
+
+    pub fn field(&self, name: &str) -> Option<&Field> {
+        find_field(&self.fields, name)
+    }
+}
+
+pub(crate) fn field(name: &str, abbr: &[&str], kind: ItemKind, description: &str) -> Field {
+    Field {
+        name: name.to_string(),
+        abbr: abbr.iter().map(ToString::to_string).collect(),
+        kind,
+        values: vec![],
+        description: Some(description.to_string()),
+    }
+}
@@ -0,0 +1,237 @@
+use crate::search::schema::SearchSchema;
+use crate::RequestImpl;
+use serde::{Deserialize, Serialize};
+use std::ops::Range;
+
+/// suggestions for the part of the query around the cursor
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct SuggestRequest {
+    pub text: String,
+    /// byte offset of the cursor in `text`
+    pub cursor: usize,
+    /// overrides the field detected from the text
+    pub field: Option<String>,
+    pub limit: u32,
+}
+
+impl RequestImpl for SuggestRequest {
+    const ROUTE: &'static str = "search/suggest";
+    const AUTH: bool = true;
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct SuggestResponse {
+    pub suggestions: Vec<Suggestion>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq)]
+pub struct Suggestion {
+    pub kind: SuggestionKind,
+    /// text which replaces `range` of the request text
+    pub value: String,
+    /// text shown to the user, can differ from the value e.g. a title in another language
+    pub label: String,
+    pub range: Range<usize>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Copy, Clone, Eq, PartialEq)]
+pub enum SuggestionKind {
+    Field,
+    /// allowed value from the schema like a status
+    Value,
+    Tag,
+    Title,
+    Author,
+    Artist,
+}
+
+/// what is typed at the cursor
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct CompletionContext {
+    /// field of the value which is typed, `None` if a field name is typed
+    pub field: Option<String>,
+    /// text between the start of the token and the cursor
+    pub partial: String,
+    /// range which gets replaced by a suggestion
+    pub range: Range<usize>,
+}
+
+impl SuggestRequest {
+    pub fn context(&self) -> CompletionContext {
+        let mut cursor = self.cursor.min(self.text.len());
+        while !self.text.is_char_boundary(cursor) {
+            cursor -= 1;
+        }
+        let is_boundary = |c: char| c.is_whitespace() || c == '(' || c == ')';
+        let start = self.text[..cursor]
+            .rfind(is_boundary)
+            .map(|i| i + 1)
+            .unwrap_or_default();
+        let end = self.text[cursor..]
+            .find(is_boundary)
+            .map(|i| i + cursor)
+            .unwrap_or(self.text.len());
+        let token = &self.text[start..cursor];
+        match token.split_once(':') {
+            Some((field, value)) => {
+                let prefix = value.len() - value.trim_start_matches(['!', '"']).len();
+                let value_start = start + field.len() + 1 + prefix;
+                CompletionContext {
+                    field: Some(self.field.clone().unwrap_or_else(|| field.to_string())),
+                    partial: self.text[value_start..cursor]
+                        .trim_end_matches('"')
+                        .to_string(),
+                    range: value_start..end,
+                }
+            }
+            None => CompletionContext {
+                field: self.field.clone(),
+                partial: token.to_string(),
+                range: start..end,
+            },
+        }
+    }
+}
+
+impl SearchSchema {
+    /// suggestions which can be answered without the server: field names and allowed values,
+    /// at most `limit` of them
+    pub fn suggest(&self, context: &CompletionContext, limit: u32) -> Vec<Suggestion> {
+        let partial = context.partial.to_lowercase();
+        let suggestion = |kind, value: &str| Suggestion {
+            kind,
+            value: value.to_string(),
+            label: value.to_string(),
+            range: context.range.clone(),
+        };
+        match &context.field {
+            None => self
+                .fields
+                .iter()
+                .filter(|field| {
+                    field.name.starts_with(&partial)
+                        || field.abbr.iter().any(|abbr| abbr.starts_with(&partial))
+                })
+                .map(|field| suggestion(SuggestionKind::Field, &format!("{}:", field.name)))
+                .take(limit as usize)
+                .collect(),
+            Some(field) => self
+                .field(field)
+                .map(|field| {
+                    field
+                        .values
+                        .iter()
+                        .filter(|value| value.to_lowercase().starts_with(&partial))
+                        .map(|value| suggestion(SuggestionKind::Value, value))
+                        .take(limit as usize)
+                        .collect()
+                })
+                .unwrap_or_default(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn context(text: &str, cursor: usize) -> CompletionContext {
+        SuggestRequest {
+            text: text.to_string(),
+            cursor,
+            field: None,
+            limit: 10,
+        }
+        .context()
+    }
+
+    fn expected(field: Option<&str>, partial: &str, range: Range<usize>) -> CompletionContext {
+        CompletionContext {
+            field: field.map(ToString::to_string),
+            partial: partial.to_string(),
+            range,
+        }
+    }
+
+    #[test]
+    fn finds_token_at_cursor() {
+        assert_eq!(context("stat", 4), expected(None, "stat", 0..4));
+        assert_eq!(context("stat sort", 2), expected(None, "st", 0..4));
+        assert_eq!(context("and:(title:x st", 15), expected(None, "st", 13..15));
+        assert_eq!(context("t:x ", 4), expected(None, "", 4..4));
+        assert_eq!(
+            context("title:ber rating:>4", 9),
+            expected(Some("title"), "ber", 6..9)
+        );
+        assert_eq!(
+            context("or:(tag:act)", 10),
+            expected(Some("tag"), "ac", 8..11)
+        );
+    }
+
+    #[test]
+    fn skips_negation_and_quotes() {
+        assert_eq!(
+            context("status:!\"ong", 12),
+            expected(Some("status"), "ong", 9..12)
+        );
+        assert_eq!(
+            context("status:!\"ong\"", 13),
+            expected(Some("status"), "ong", 9..13)
+        );
+        assert_eq!(context("s:!", 3), expected(Some("s"), "", 3..3));
+    }
+
+    #[test]
+    fn clamps_cursor() {
+        assert_eq!(context("tag:ac", 100), expected(Some("tag"), "ac", 4..6));
+        assert_eq!(context("", 1), expected(None, "", 0..0));
+        // inside the two bytes of ä
+        assert_eq!(context("t:ä", 3), expected(Some("t"), "", 2..4));
+    }
+
+    #[test]
+    fn field_overrides_text() {
+        let request = SuggestRequest {
+            text: "fav".to_string(),
+            cursor: 3,
+            field: Some("tag".to_string()),
+            limit: 10,
+        };
+        assert_eq!(request.context(), expected(Some("tag"), "fav", 0..3));
+    }
+
+    #[test]
+    fn suggests_fields_and_values() {
+        let schema = SearchSchema::internal();
+        let values = |context: &CompletionContext, limit| {
+            schema
+                .suggest(context, limit)
+                .into_iter()
+                .map(|v| v.value)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            values(&context("a", 1), 10),
+            vec!["author:", "artist:", "added:"]
+        );
+        assert_eq!(values(&context("a", 1), 2), vec!["author:", "artist:"]);
+        assert_eq!(values(&context("", 0), 0), Vec::<String>::new());
+        assert_eq!(
+            schema.suggest(&context("", 0), 100).len(),
+            schema.fields.len()
+        );
+        let suggestions = schema.suggest(&context("s:!o", 4), 10);
+        assert_eq!(
+            suggestions,
+            vec![Suggestion {
+                kind: SuggestionKind::Value,
+                value: "ongoing".to_string(),
+                label: "ongoing".to_string(),
+                range: 3..4,
+            }]
+        );
+        assert_eq!(values(&context("s:", 2), 2), vec!["dropped", "hiatus"]);
+        assert!(values(&context("title:o", 7), 10).is_empty());
+    }
+}
@@ -0,0 +1,35 @@
+//! constructors for query trees shared by the tests of the search modules
+
+use crate::search::{Array, Item, ItemData, ItemOrArray, ItemValue};
+
+pub fn item(not: bool, name: &str, value: ItemValue) -> ItemOrArray {
+    ItemOrArray::Item(Item {
+        not,
+        data: ItemData {
+            name: name.to_string(),
+            value,
+        },
+    })
+}
+
+pub fn array(or: bool, items: Vec<ItemOrArray>) -> ItemOrArray {
+    ItemOrArray::Array(Array { or, items })
+}
+
+pub fn range(min: ItemValue, max: ItemValue, inclusive: bool) -> ItemValue {
+    ItemValue::Range {
+        min: Box::new(min),
+        max: Box::new(max),
+        inclusive,
+    }
+}
+
+/// set of string values
+pub fn strings(values: &[&str]) -> ItemValue {
+    ItemValue::Set(
+        values
+            .iter()
+            .map(|v| ItemValue::String(v.to_string()))
+            .collect(),
+    )
+}
@@ -0,0 +1,375 @@
+use crate::error::{ApiErr, ApiErrorType};
+use crate::search::parser::closest_match;
+use crate::search::schema::SearchSchema;
+use crate::search::{Item, ItemKind, ItemOrArray, ItemValue, SearchRequest};
+use serde::{Deserialize, Serialize};
+use std::fmt::{Display, Formatter};
+
+/// problem found in a [`SearchRequest`]
+///
+/// `path` contains the indices of the arrays leading to the offending item
+#[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq)]
+pub enum ValidationError {
+    UnknownField {
+        path: Vec<usize>,
+        name: String,
+        suggestion: Option<String>,
+    },
+    KindMismatch {
+        path: Vec<usize>,
+        field: String,
+        expected: ItemKind,
+        found: ItemKind,
+    },
+    ValueNotAllowed {
+        path: Vec<usize>,
+        field: String,
+        value: String,
+        allowed: Vec<String>,
+    },
+    TooDeep {
+        path: Vec<usize>,
+        max: usize,
+    },
+    LimitOutOfRange {
+        limit: u32,
+        max: u32,
+    },
+}
+
+impl Display for ValidationError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ValidationError::UnknownField {
+                name, suggestion, ..
+            } => {
+                write!(f, "Unknown field \"{name}\"")?;
+                if let Some(suggestion) = suggestion {
+                    write!(f, ", did you mean \"{suggestion}\"?")?;
+                }
+                Ok(())
+            }
+            ValidationError::KindMismatch {
+                field,
+                expected,
+                found,
+                ..
+            } => write!(f, "{field} expects {expected:?} but got {found:?}"),
+            ValidationError::ValueNotAllowed {
+                field,
+                value,
+                allowed,
+                ..
+            } => write!(
+                f,
+                "\"{value}\" is not allowed for {field}, expected one of {}",
+                allowed.join(", ")
+            ),
+            ValidationError::TooDeep { max, .. } => {
+                write!(f, "Query is nested deeper than {max} levels")
+            }
+            ValidationError::LimitOutOfRange { limit, max } => {
+                write!(f, "Limit {limit} is not between 1 and {max}")
+            }
+        }
+    }
+}
+
+impl From<Vec<ValidationError>> for ApiErr {
+    fn from(value: Vec<ValidationError>) -> Self {
+        ApiErr {
+            message: Some(
+                value
+                    .iter()
+                    .map(|v| v.to_string())
+                    .collect::<Vec<_>>()
+                    .join("\n"),
+            ),
+            cause: None,
+            err_type: ApiErrorType::InvalidInput,
+        }
+    }
+}
+
+impl SearchRequest {
+    /// checks the whole request and returns every problem at once
+    pub fn validate(&self, schema: &SearchSchema) -> Result<(), Vec<ValidationError>> {
+        let mut errors = vec![];
+        if self.limit == 0 || self.limit > schema.max_limit {
+            errors.push(ValidationError::LimitOutOfRange {
+                limit: self.limit,
+                max: schema.max_limit,
+            });
+        }
+        if let Err(query) = self.query.validate(schema) {
+            errors.extend(query);
+        }
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+}
+
+impl ItemOrArray {
+    pub fn validate(&self, schema: &SearchSchema) -> Result<(), Vec<ValidationError>> {
+        let mut errors = vec![];
+        validate_query(self, schema, &mut vec![], &mut errors);
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+}
+
+fn validate_query(
+    query: &ItemOrArray,
+    schema: &SearchSchema,
+    path: &mut Vec<usize>,
+    errors: &mut Vec<ValidationError>,
+) {
+    match query {
+        ItemOrArray::Item(item) => validate_item(item, schema, path, errors),
+        ItemOrArray::Array(array) => {
+            if path.len() >= schema.max_depth {
+                errors.push(ValidationError::TooDeep {
+                    path: path.clone(),
+                    max: schema.max_depth,
+                });
+                return;
+            }
+            for (i, item) in array.items.iter().enumerate() {
+                path.push(i);
+                validate_query(item, schema, path, errors);
+                path.pop();
+            }
+        }
+    }
+}
+
+fn validate_item(
+    item: &Item,
+    schema: &SearchSchema,
+    path: &[usize],
+    errors: &mut Vec<ValidationError>,
+) {
+    let name = &item.data.name;
+    let field = match schema.field(name) {
+        Some(field) => field,
+        None => {
+            // an allowed value like `ongoing:` can't be searched without its field
+            let owner = schema
+                .fields
+                .iter()
+                .find(|field| field.values.iter().any(|v| v.eq_ignore_ascii_case(name)));
+            let suggestion = match (&item.data.value, owner) {
+                (ItemValue::None, Some(field)) => Some(format!("{}:{name}", field.name)),
+                _ => {
+                    let candidates = schema.fields.iter().map(|field| field.name.as_str());
+                    closest_match(name, candidates).map(ToString::to_string)
+                }
+            };
+            errors.push(ValidationError::UnknownField {
+                path: path.to_vec(),
+                name: name.to_string(),
+                suggestion,
+            });
+            return;
+        }
+    };
+    let (expected, values) = match &item.data.value {
+        ItemValue::Range { min, max, .. } => (field.kind.base(), vec![&**min, &**max]),
+        ItemValue::Set(values) => (field.kind.base(), values.iter().collect()),
+        value => (field.kind, vec![value]),
+    };
+    for value in values {
+        let found = match value.kind() {
+            Some(kind) => kind,
+            None => continue,
+        };
+        if found != expected {
+            errors.push(ValidationError::KindMismatch {
+                path: path.to_vec(),
+                field: field.name.clone(),
+                expected,
+                found,
+            });
+            return;
+        }
+        if let ItemValue::String(value) = value {
+            if !field.values.is_empty()
+                && !field.values.iter().any(|v| v.eq_ignore_ascii_case(value))
+            {
+                errors.push(ValidationError::ValueNotAllowed {
+                    path: path.to_vec(),
+                    field: field.name.clone(),
+                    value: value.to_string(),
+                    allowed: field.values.clone(),
+                });
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::search::parser::parse_query;
+    use crate::search::test_util::{array, item, range, strings};
+    use crate::search::{ItemData, Order, Status};
+
+    fn validate(query: &str) -> Result<(), Vec<ValidationError>> {
+        let schema = SearchSchema::internal();
+        parse_query(query, &schema.fields)
+            .unwrap()
+            .validate(&schema)
+    }
+
+    #[test]
+    fn accepts_known_fields() {
+        assert_eq!(
+            validate("and:(t:x status:!ongoing rating:>=4 favorite: or:())"),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn rejects_values_without_field() {
+        assert_eq!(
+            validate("and:(ongoing: titl:)"),
+            Err(vec![
+                ValidationError::UnknownField {
+                    path: vec![0],
+                    name: "ongoing".to_string(),
+                    suggestion: Some("status:ongoing".to_string()),
+                },
+                ValidationError::UnknownField {
+                    path: vec![1],
+                    name: "titl".to_string(),
+                    suggestion: Some("title".to_string()),
+                },
+            ])
+        );
+    }
+
+    #[test]
+    fn reports_every_problem() {
+        let schema = SearchSchema::internal();
+        let mut query = parse_query("status:finished favorite:true", &schema.fields).unwrap();
+        if let ItemOrArray::Array(array) = &mut query {
+            // kinds only mismatch in trees which weren't parsed with the schema
+            array.items.push(ItemOrArray::Item(Item::new(ItemData {
+                name: "rating".to_string(),
+                value: ItemValue::Bool(true),
+            })));
+        }
+        let request = SearchRequest {
+            order: Order::Created,
+            desc: false,
+            then: vec![],
+            seed: None,
+            limit: schema.max_limit + 1,
+            page: 1,
+            query,
+            facets: vec![],
+            highlight: false,
+        };
+        assert_eq!(
+            request.validate(&schema),
+            Err(vec![
+                ValidationError::LimitOutOfRange {
+                    limit: schema.max_limit + 1,
+                    max: schema.max_limit,
+                },
+                ValidationError::ValueNotAllowed {
+                    path: vec![0],
+                    field: "status".to_string(),
+                    value: "finished".to_string(),
+                    allowed: Status::ALL.iter().map(ToString::to_string).collect(),
+                },
+                ValidationError::KindMismatch {
+                    path: vec![2],
+                    field: "rating".to_string(),
+                    expected: ItemKind::CmpFloat,
+                    found: ItemKind::Bool,
+                },
+            ])
+        );
+    }
+
+    #[test]
+    fn checks_kinds_inside_ranges_and_sets() {
+        assert_eq!(
+            validate("rating:3..4 ch:{1,2} status:{ongoing,hiatus}"),
+            Ok(())
+        );
+        let schema = SearchSchema::internal();
+        let query = array(
+            false,
+            vec![
+                item(
+                    false,
+                    "rating",
+                    range(ItemValue::Int(3), ItemValue::Int(4), false),
+                ),
+                item(
+                    false,
+                    "chapters",
+                    range(
+                        ItemValue::Int(3),
+                        ItemValue::CmpInt {
+                            eq: false,
+                            bigger: true,
+                            value: 4,
+                        },
+                        true,
+                    ),
+                ),
+                item(false, "status", strings(&["ongoing", "done"])),
+            ],
+        );
+        assert_eq!(
+            query.validate(&schema),
+            Err(vec![
+                ValidationError::KindMismatch {
+                    path: vec![0],
+                    field: "rating".to_string(),
+                    expected: ItemKind::Float,
+                    found: ItemKind::Int,
+                },
+                ValidationError::KindMismatch {
+                    path: vec![1],
+                    field: "chapters".to_string(),
+                    expected: ItemKind::Int,
+                    found: ItemKind::CmpInt,
+                },
+                ValidationError::ValueNotAllowed {
+                    path: vec![2],
+                    field: "status".to_string(),
+                    value: "done".to_string(),
+                    allowed: Status::ALL.iter().map(ToString::to_string).collect(),
+                },
+            ])
+        );
+    }
+
+    #[test]
+    fn limits_depth() {
+        let schema = SearchSchema::internal();
+        let nested = |depth| {
+            (0..depth).fold(parse_query("t:x", &schema.fields).unwrap(), |query, _| {
+                array(false, vec![query])
+            })
+        };
+        assert_eq!(nested(schema.max_depth).validate(&schema), Ok(()));
+        assert_eq!(
+            nested(schema.max_depth + 1).validate(&schema),
+            Err(vec![ValidationError::TooDeep {
+                path: vec![0; schema.max_depth],
+                max: schema.max_depth,
+            }])
+        );
+    }
+}
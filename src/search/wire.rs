@@ -0,0 +1,657 @@
+use crate::error::{ApiErr, ApiErrorType};
+use crate::search::{Array, Item, ItemData, ItemOrArray, ItemValue};
+use serde::{Deserialize, Serialize};
+
+/// current version of [`WireQuery`]
+pub const WIRE_VERSION: u32 = 1;
+
+/// versioned and explicitly tagged json form of a query tree
+///
+/// ```json
+/// {"v": 1, "query": {"type": "and", "items": [
+///     {"type": "item", "field": "status", "not": false, "value": {"kind": "string", "value": "ongoing"}}
+/// ]}}
+/// ```
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct WireQuery {
+    pub v: u32,
+    pub query: WireNode,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum WireNode {
+    And {
+        items: Vec<WireNode>,
+    },
+    Or {
+        items: Vec<WireNode>,
+    },
+    Item {
+        field: String,
+        not: bool,
+        value: WireValue,
+    },
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum WireValue {
+    None,
+    Bool {
+        value: bool,
+    },
+    Int {
+        value: i64,
+    },
+    Float {
+        value: f64,
+    },
+    String {
+        value: String,
+    },
+    CmpInt {
+        op: CmpOp,
+        value: i64,
+    },
+    CmpFloat {
+        op: CmpOp,
+        value: f32,
+    },
+    /// in the syntax of [`crate::search::date::DateQuery`] e.g. `>=-1mo`
+    Date {
+        value: String,
+    },
+    Range {
+        min: Box<WireValue>,
+        max: Box<WireValue>,
+        inclusive: bool,
+    },
+    Set {
+        values: Vec<WireValue>,
+    },
+}
+
+#[derive(Serialize, Deserialize, Debug, Copy, Clone, Eq, PartialEq)]
+pub enum CmpOp {
+    #[serde(rename = ">")]
+    Gt,
+    #[serde(rename = ">=")]
+    Ge,
+    #[serde(rename = "<")]
+    Lt,
+    #[serde(rename = "<=")]
+    Le,
+}
+
+impl CmpOp {
+    fn new(eq: bool, bigger: bool) -> Self {
+        match (bigger, eq) {
+            (true, false) => CmpOp::Gt,
+            (true, true) => CmpOp::Ge,
+            (false, false) => CmpOp::Lt,
+            (false, true) => CmpOp::Le,
+        }
+    }
+
+    /// `(eq, bigger)`
+    fn flags(&self) -> (bool, bool) {
+        match self {
+            CmpOp::Gt => (false, true),
+            CmpOp::Ge => (true, true),
+            CmpOp::Lt => (false, false),
+            CmpOp::Le => (true, false),
+        }
+    }
+}
+
+/// accepts the versioned format and the untagged format used before it
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(untagged)]
+pub enum AnyQuery {
+    Versioned(WireQuery),
+    Legacy(ItemOrArray),
+}
+
+impl AnyQuery {
+    pub fn into_query(self) -> Result<ItemOrArray, ApiErr> {
+        match self {
+            AnyQuery::Versioned(query) => query.try_into(),
+            AnyQuery::Legacy(query) => Ok(query),
+        }
+    }
+}
+
+/// serde `with` module for [`ItemOrArray`] fields of requests
+///
+/// writes a [`WireQuery`] and reads both formats of [`AnyQuery`],
+/// so clients sending the untagged format keep working
+pub mod versioned {
+    use super::{AnyQuery, WireQuery};
+    use crate::search::ItemOrArray;
+    use serde::de::Error;
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    pub fn serialize<S: Serializer>(query: &ItemOrArray, serializer: S) -> Result<S::Ok, S::Error> {
+        WireQuery::from(query).serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<ItemOrArray, D::Error> {
+        AnyQuery::deserialize(deserializer)?
+            .into_query()
+            .map_err(|e| match &e.cause {
+                Some(cause) => D::Error::custom(format!("{}: {cause}", e.message())),
+                None => D::Error::custom(e.message()),
+            })
+    }
+}
+
+impl From<&ItemOrArray> for WireQuery {
+    fn from(value: &ItemOrArray) -> Self {
+        Self {
+            v: WIRE_VERSION,
+            query: value.into(),
+        }
+    }
+}
+
+impl From<&ItemOrArray> for WireNode {
+    fn from(value: &ItemOrArray) -> Self {
+        match value {
+            ItemOrArray::Item(item) => WireNode::Item {
+                field: item.data.name.clone(),
+                not: item.not,
+                value: (&item.data.value).into(),
+            },
+            ItemOrArray::Array(Array { or, items }) => {
+                let items = items.iter().map(Into::into).collect();
+                match or {
+                    true => WireNode::Or { items },
+                    false => WireNode::And { items },
+                }
+            }
+        }
+    }
+}
+
+impl From<&ItemValue> for WireValue {
+    fn from(value: &ItemValue) -> Self {
+        match value {
+            ItemValue::None => WireValue::None,
+            ItemValue::Bool(value) => WireValue::Bool { value: *value },
+            ItemValue::Int(value) => WireValue::Int { value: *value },
+            ItemValue::Float(value) => WireValue::Float { value: *value },
+            ItemValue::String(value) => WireValue::String {
+                value: value.clone(),
+            },
+            ItemValue::CmpInt { eq, bigger, value } => WireValue::CmpInt {
+                op: CmpOp::new(*eq, *bigger),
+                value: *value,
+            },
+            ItemValue::CmpFloat { eq, bigger, value } => WireValue::CmpFloat {
+                op: CmpOp::new(*eq, *bigger),
+                value: *value,
+            },
+            ItemValue::Date(value) => WireValue::Date {
+                value: value.to_string(),
+            },
+            ItemValue::Range {
+                min,
+                max,
+                inclusive,
+            } => WireValue::Range {
+                min: Box::new((&**min).into()),
+                max: Box::new((&**max).into()),
+                inclusive: *inclusive,
+            },
+            ItemValue::Set(values) => WireValue::Set {
+                values: values.iter().map(Into::into).collect(),
+            },
+        }
+    }
+}
+
+impl TryFrom<WireQuery> for ItemOrArray {
+    type Error = ApiErr;
+
+    fn try_from(value: WireQuery) -> Result<Self, Self::Error> {
+        if value.v != WIRE_VERSION {
+            return Err(ApiErr {
+                message: Some("Unsupported query version".to_string()),
+                cause: Some(value.v.to_string()),
+                err_type: ApiErrorType::InvalidInput,
+            });
+        }
+        value.query.try_into()
+    }
+}
+
+impl TryFrom<WireNode> for ItemOrArray {
+    type Error = ApiErr;
+
+    fn try_from(value: WireNode) -> Result<Self, Self::Error> {
+        let array = |or, items: Vec<WireNode>| {
+            Ok(ItemOrArray::Array(Array {
+                or,
+                items: items
+                    .into_iter()
+                    .map(TryInto::try_into)
+                    .collect::<Result<_, ApiErr>>()?,
+            }))
+        };
+        match value {
+            WireNode::And { items } => array(false, items),
+            WireNode::Or { items } => array(true, items),
+            WireNode::Item { field, not, value } => Ok(ItemOrArray::Item(Item {
+                not,
+                data: ItemData {
+                    name: field,
+                    value: value.try_into()?,
+                },
+            })),
+        }
+    }
+}
+
+impl TryFrom<WireValue> for ItemValue {
+    type Error = ApiErr;
+
+    fn try_from(value: WireValue) -> Result<Self, Self::Error> {
+        Ok(match value {
+            WireValue::None => ItemValue::None,
+            WireValue::Bool { value } => ItemValue::Bool(value),
+            WireValue::Int { value } => ItemValue::Int(value),
+            WireValue::Float { value } => ItemValue::Float(value),
+            WireValue::String { value } => ItemValue::String(value),
+            WireValue::CmpInt { op, value } => {
+                let (eq, bigger) = op.flags();
+                ItemValue::CmpInt { eq, bigger, value }
+            }
+            WireValue::CmpFloat { op, value } => {
+                let (eq, bigger) = op.flags();
+                ItemValue::CmpFloat { eq, bigger, value }
+            }
+            WireValue::Date { value } => ItemValue::Date(value.parse().map_err(|e| ApiErr {
+                message: Some("Invalid date".to_string()),
+                cause: Some(e),
+                err_type: ApiErrorType::InvalidInput,
+            })?),
+            WireValue::Range {
+                min,
+                max,
+                inclusive,
+            } => ItemValue::Range {
+                min: Box::new((*min).try_into()?),
+                max: Box::new((*max).try_into()?),
+                inclusive,
+            },
+            WireValue::Set { values } => ItemValue::Set(
+                values
+                    .into_iter()
+                    .map(TryInto::try_into)
+                    .collect::<Result<_, _>>()?,
+            ),
+        })
+    }
+}
+
+/// JSON Schema (draft 2020-12) of [`WireQuery`] for generating client types
+pub const WIRE_JSON_SCHEMA: &str = r##"{
+  "$schema": "https://json-schema.org/draft/2020-12/schema",
+  "$id": "WireQuery",
+  "title": "WireQuery",
+  "type": "object",
+  "required": ["v", "query"],
+  "properties": {
+    "v": { "const": 1 },
+    "query": { "$ref": "#/$defs/WireNode" }
+  },
+  "additionalProperties": false,
+  "$defs": {
+    "WireNode": {
+      "oneOf": [
+        {
+          "type": "object",
+          "required": ["type", "items"],
+          "properties": {
+            "type": { "enum": ["and", "or"] },
+            "items": { "type": "array", "items": { "$ref": "#/$defs/WireNode" } }
+          },
+          "additionalProperties": false
+        },
+        {
+          "type": "object",
+          "required": ["type", "field", "not", "value"],
+          "properties": {
+            "type": { "const": "item" },
+            "field": { "type": "string" },
+            "not": { "type": "boolean" },
+            "value": { "$ref": "#/$defs/WireValue" }
+          },
+          "additionalProperties": false
+        }
+      ]
+    },
+    "CmpOp": { "enum": [">", ">=", "<", "<="] },
+    "WireValue": {
+      "oneOf": [
+        {
+          "type": "object",
+          "required": ["kind"],
+          "properties": { "kind": { "const": "none" } },
+          "additionalProperties": false
+        },
+        {
+          "type": "object",
+          "required": ["kind", "value"],
+          "properties": { "kind": { "const": "bool" }, "value": { "type": "boolean" } },
+          "additionalProperties": false
+        },
+        {
+          "type": "object",
+          "required": ["kind", "value"],
+          "properties": { "kind": { "const": "int" }, "value": { "type": "integer" } },
+          "additionalProperties": false
+        },
+        {
+          "type": "object",
+          "required": ["kind", "value"],
+          "properties": { "kind": { "const": "float" }, "value": { "type": "number" } },
+          "additionalProperties": false
+        },
+        {
+          "type": "object",
+          "required": ["kind", "value"],
+          "properties": { "kind": { "const": "string" }, "value": { "type": "string" } },
+          "additionalProperties": false
+        },
+        {
+          "type": "object",
+          "required": ["kind", "op", "value"],
+          "properties": {
+            "kind": { "const": "cmp_int" },
+            "op": { "$ref": "#/$defs/CmpOp" },
+            "value": { "type": "integer" }
+          },
+          "additionalProperties": false
+        },
+        {
+          "type": "object",
+          "required": ["kind", "op", "value"],
+          "properties": {
+            "kind": { "const": "cmp_float" },
+            "op": { "$ref": "#/$defs/CmpOp" },
+            "value": { "type": "number" }
+          },
+          "additionalProperties": false
+        },
+        {
+          "type": "object",
+          "required": ["kind", "value"],
+          "properties": { "kind": { "const": "date" }, "value": { "type": "string" } },
+          "additionalProperties": false
+        },
+        {
+          "type": "object",
+          "required": ["kind", "min", "max", "inclusive"],
+          "properties": {
+            "kind": { "const": "range" },
+            "min": { "$ref": "#/$defs/WireValue" },
+            "max": { "$ref": "#/$defs/WireValue" },
+            "inclusive": { "type": "boolean" }
+          },
+          "additionalProperties": false
+        },
+        {
+          "type": "object",
+          "required": ["kind", "values"],
+          "properties": {
+            "kind": { "const": "set" },
+            "values": { "type": "array", "items": { "$ref": "#/$defs/WireValue" } }
+          },
+          "additionalProperties": false
+        }
+      ]
+    }
+  }
+}"##;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::search::date::DateQuery;
+    use crate::search::schema::SearchSchema;
+    use crate::search::test_util::{array, item, range, strings};
+    use crate::search::{Order, SearchRequest};
+    use serde_json::{json, Value};
+    use std::collections::BTreeSet;
+
+    fn cmp(eq: bool, bigger: bool) -> ItemValue {
+        ItemValue::CmpInt {
+            eq,
+            bigger,
+            value: 3,
+        }
+    }
+
+    /// every variant of [`WireValue`], [`WireNode`] and [`CmpOp`] with its json
+    fn values() -> Vec<(ItemValue, Value)> {
+        vec![
+            (ItemValue::None, json!({"kind": "none"})),
+            (
+                ItemValue::Bool(true),
+                json!({"kind": "bool", "value": true}),
+            ),
+            (ItemValue::Int(-2), json!({"kind": "int", "value": -2})),
+            (
+                ItemValue::Float(1.5),
+                json!({"kind": "float", "value": 1.5}),
+            ),
+            (
+                ItemValue::String("x".to_string()),
+                json!({"kind": "string", "value": "x"}),
+            ),
+            (
+                cmp(false, true),
+                json!({"kind": "cmp_int", "op": ">", "value": 3}),
+            ),
+            (
+                cmp(true, true),
+                json!({"kind": "cmp_int", "op": ">=", "value": 3}),
+            ),
+            (
+                cmp(false, false),
+                json!({"kind": "cmp_int", "op": "<", "value": 3}),
+            ),
+            (
+                cmp(true, false),
+                json!({"kind": "cmp_int", "op": "<=", "value": 3}),
+            ),
+            (
+                ItemValue::CmpFloat {
+                    eq: true,
+                    bigger: true,
+                    value: 4.5,
+                },
+                json!({"kind": "cmp_float", "op": ">=", "value": 4.5}),
+            ),
+            (
+                ItemValue::Date(">=-1mo".parse::<DateQuery>().unwrap()),
+                json!({"kind": "date", "value": ">=-1mo"}),
+            ),
+            (
+                range(ItemValue::Int(1), ItemValue::Int(2), true),
+                json!({
+                    "kind": "range",
+                    "min": {"kind": "int", "value": 1},
+                    "max": {"kind": "int", "value": 2},
+                    "inclusive": true
+                }),
+            ),
+            (
+                strings(&["a"]),
+                json!({"kind": "set", "values": [{"kind": "string", "value": "a"}]}),
+            ),
+        ]
+    }
+
+    fn query() -> (ItemOrArray, Value) {
+        let (values, json): (Vec<_>, Vec<_>) = values().into_iter().unzip();
+        let query = array(
+            false,
+            vec![
+                array(
+                    true,
+                    values
+                        .into_iter()
+                        .map(|v| item(false, "title", v))
+                        .collect(),
+                ),
+                item(true, "tag", ItemValue::String("gore".to_string())),
+            ],
+        );
+        let items = json
+            .into_iter()
+            .map(|value| json!({"type": "item", "field": "title", "not": false, "value": value}))
+            .collect::<Vec<_>>();
+        let json = json!({"v": 1, "query": {"type": "and", "items": [
+            {"type": "or", "items": items},
+            {"type": "item", "field": "tag", "not": true, "value": {"kind": "string", "value": "gore"}}
+        ]}});
+        (query, json)
+    }
+
+    #[test]
+    fn every_variant_round_trips() {
+        let (query, json) = query();
+        let wire = WireQuery::from(&query);
+        assert_eq!(serde_json::to_value(&wire).unwrap(), json);
+        let parsed = serde_json::from_value::<WireQuery>(json).unwrap();
+        assert_eq!(parsed, wire);
+        assert_eq!(ItemOrArray::try_from(parsed).unwrap(), query);
+    }
+
+    /// collects `const` and `enum` strings of the `tag` property of every `oneOf` branch
+    /// together with the properties the branch requires
+    fn schema_branches(schema: &Value, def: &str, tag: &str) -> BTreeSet<(String, Vec<String>)> {
+        let mut out = BTreeSet::new();
+        for branch in schema["$defs"][def]["oneOf"].as_array().unwrap() {
+            let property = &branch["properties"][tag];
+            let names = match &property["const"] {
+                Value::String(name) => vec![Value::String(name.clone())],
+                _ => property["enum"].as_array().unwrap().clone(),
+            };
+            let mut required = branch["required"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|v| v.as_str().unwrap().to_string())
+                .collect::<Vec<_>>();
+            required.sort();
+            let mut properties = branch["properties"]
+                .as_object()
+                .unwrap()
+                .keys()
+                .cloned()
+                .collect::<Vec<_>>();
+            properties.sort();
+            assert_eq!(required, properties, "{def} {names:?}");
+            for name in names {
+                out.insert((name.as_str().unwrap().to_string(), required.clone()));
+            }
+        }
+        out
+    }
+
+    /// tag and sorted keys of every object with the `tag` property in `json`
+    fn serde_objects(json: &Value, tag: &str, out: &mut BTreeSet<(String, Vec<String>)>) {
+        match json {
+            Value::Object(map) => {
+                if let Some(Value::String(name)) = map.get(tag) {
+                    let mut keys = map.keys().cloned().collect::<Vec<_>>();
+                    keys.sort();
+                    out.insert((name.clone(), keys));
+                }
+                map.values().for_each(|v| serde_objects(v, tag, out));
+            }
+            Value::Array(values) => values.iter().for_each(|v| serde_objects(v, tag, out)),
+            _ => {}
+        }
+    }
+
+    #[test]
+    fn json_schema_matches_serde() {
+        let schema = serde_json::from_str::<Value>(WIRE_JSON_SCHEMA).unwrap();
+        let json = query().1;
+        assert_eq!(schema["properties"]["v"]["const"], json!(WIRE_VERSION));
+        for (def, tag) in [("WireValue", "kind"), ("WireNode", "type")] {
+            let mut serialized = BTreeSet::new();
+            serde_objects(&json, tag, &mut serialized);
+            assert_eq!(schema_branches(&schema, def, tag), serialized, "{def}");
+        }
+        let ops = values()
+            .iter()
+            .filter_map(|(_, json)| Some(json.get("op")?.as_str()?.to_string()))
+            .collect::<BTreeSet<_>>();
+        let schema_ops = schema["$defs"]["CmpOp"]["enum"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|v| v.as_str().unwrap().to_string())
+            .collect::<BTreeSet<_>>();
+        assert_eq!(ops, schema_ops);
+    }
+
+    fn request(query: ItemOrArray) -> SearchRequest {
+        SearchRequest {
+            order: Order::Created,
+            desc: false,
+            then: vec![],
+            seed: None,
+            limit: 10,
+            page: 1,
+            query,
+            facets: vec![],
+            highlight: false,
+        }
+    }
+
+    #[test]
+    fn requests_use_the_versioned_format() {
+        let (query, json) = query();
+        let request = request(query);
+        let value = serde_json::to_value(&request).unwrap();
+        assert_eq!(value["query"], json);
+        assert_eq!(
+            serde_json::from_value::<SearchRequest>(value).unwrap(),
+            request
+        );
+    }
+
+    #[test]
+    fn requests_accept_the_legacy_format() {
+        let query = crate::search::parser::parse_query(
+            "and:(status:ongoing or:(rating:>=4 ch:1..=3))",
+            &SearchSchema::internal().fields,
+        )
+        .unwrap();
+        let mut value = serde_json::to_value(request(query.clone())).unwrap();
+        value["query"] = serde_json::to_value(&query).unwrap();
+        assert_eq!(value["query"]["or"], json!(false));
+        let parsed = serde_json::from_value::<SearchRequest>(value).unwrap();
+        assert_eq!(parsed.query, query);
+    }
+
+    #[test]
+    fn rejects_unknown_versions() {
+        let mut value = serde_json::to_value(request(query().0)).unwrap();
+        value["query"]["v"] = json!(2);
+        let err = serde_json::from_value::<SearchRequest>(value).unwrap_err();
+        assert!(
+            err.to_string().contains("Unsupported query version: 2"),
+            "{err}"
+        );
+    }
+}
@@ -0,0 +1,337 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
+use std::ops::{Deref, DerefMut};
+use std::str::FromStr;
+
+/// titles of a manga by language
+///
+/// the keys are [`TitleKey`]s like `ja`, `ja-ro` for the romanized form
+/// and `ja*` to mark the original language
+#[derive(Serialize, Deserialize, Debug, Clone, Default, Eq, PartialEq)]
+#[serde(transparent)]
+pub struct TitleSet(pub HashMap<String, Vec<String>>);
+
+/// key of a [`TitleSet`]
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub struct TitleKey {
+    pub language: String,
+    pub script: Script,
+    pub original: bool,
+}
+
+#[derive(Serialize, Deserialize, Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum Script {
+    Native,
+    /// written in latin letters, e.g. `Shingeki no Kyojin`
+    Romanized,
+}
+
+/// which scripts are accepted, in the listed order
+#[derive(Serialize, Deserialize, Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum ScriptPreference {
+    Native,
+    Romanized,
+    NativeFirst,
+    RomanizedFirst,
+}
+
+impl ScriptPreference {
+    fn scripts(&self) -> &'static [Script] {
+        match self {
+            ScriptPreference::Native => &[Script::Native],
+            ScriptPreference::Romanized => &[Script::Romanized],
+            ScriptPreference::NativeFirst => &[Script::Native, Script::Romanized],
+            ScriptPreference::RomanizedFirst => &[Script::Romanized, Script::Native],
+        }
+    }
+}
+
+/// one entry of a fallback chain, the first one with a title wins
+#[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq, Hash)]
+pub enum LanguagePreference {
+    Language {
+        language: String,
+        script: ScriptPreference,
+    },
+    /// the language the manga was published in
+    Original(ScriptPreference),
+    /// any title, the original language first
+    Any,
+}
+
+impl LanguagePreference {
+    pub fn language(language: &str) -> Self {
+        Self::Language {
+            language: language.to_string(),
+            script: ScriptPreference::NativeFirst,
+        }
+    }
+}
+
+impl Display for TitleKey {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.language)?;
+        if self.script == Script::Romanized {
+            write!(f, "-ro")?;
+        }
+        if self.original {
+            write!(f, "*")?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for TitleKey {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (key, original) = match s.strip_suffix('*') {
+            Some(key) => (key, true),
+            None => (s, false),
+        };
+        let (language, script) = match key.strip_suffix("-ro") {
+            Some(language) => (language, Script::Romanized),
+            None => (key, Script::Native),
+        };
+        if language.is_empty() {
+            return Err(format!("Failed to parse title key: {}", s));
+        }
+        Ok(Self {
+            language: language.to_lowercase(),
+            script,
+            original,
+        })
+    }
+}
+
+impl TitleSet {
+    /// entries with a parsable key and at least one title
+    pub fn entries(&self) -> impl Iterator<Item = (TitleKey, &Vec<String>)> {
+        self.0
+            .iter()
+            .filter(|(_, titles)| !titles.is_empty())
+            .filter_map(|(key, titles)| Some((key.parse().ok()?, titles)))
+    }
+
+    pub fn original_language(&self) -> Option<String> {
+        self.entries()
+            .find(|(key, _)| key.original)
+            .map(|(key, _)| key.language)
+    }
+
+    pub fn get_titles(&self, language: &str, script: Script) -> Option<&Vec<String>> {
+        let language = language.to_lowercase();
+        self.entries()
+            .find(|(key, _)| key.language == language && key.script == script)
+            .map(|(_, titles)| titles)
+    }
+
+    /// first title of the first preference which has one,
+    /// falls back to any title if no preference matches
+    pub fn best(&self, preferences: &[LanguagePreference]) -> Option<&str> {
+        let original = self.original_language();
+        preferences
+            .iter()
+            .find_map(|preference| match preference {
+                LanguagePreference::Language { language, script } => self.pick(language, *script),
+                LanguagePreference::Original(script) => self.pick(original.as_ref()?, *script),
+                LanguagePreference::Any => self.any(),
+            })
+            .or_else(|| self.any())
+    }
+
+    fn pick(&self, language: &str, script: ScriptPreference) -> Option<&str> {
+        script
+            .scripts()
+            .iter()
+            .find_map(|script| self.get_titles(language, *script)?.first())
+            .map(|v| v.as_str())
+    }
+
+    /// deterministic choice independent of the map order,
+    /// keys like `ja` and `JA` which parse the same are ordered by their titles
+    fn any(&self) -> Option<&str> {
+        self.entries()
+            .min_by_key(|(key, titles)| {
+                (
+                    !key.original,
+                    key.script == Script::Romanized,
+                    key.language.clone(),
+                    *titles,
+                )
+            })
+            .and_then(|(_, titles)| titles.first())
+            .map(|v| v.as_str())
+    }
+}
+
+impl Deref for TitleSet {
+    type Target = HashMap<String, Vec<String>>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl DerefMut for TitleSet {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+impl From<HashMap<String, Vec<String>>> for TitleSet {
+    fn from(value: HashMap<String, Vec<String>>) -> Self {
+        Self(value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn titles(entries: &[(&str, &str)]) -> TitleSet {
+        let mut titles = TitleSet::default();
+        for (key, title) in entries {
+            titles
+                .entry(key.to_string())
+                .or_default()
+                .push(title.to_string());
+        }
+        titles
+    }
+
+    fn shingeki() -> TitleSet {
+        titles(&[
+            ("en", "Attack on Titan"),
+            ("ja*", "進撃の巨人"),
+            ("ja-ro*", "Shingeki no Kyojin"),
+            ("de", "Angriff auf Titan"),
+            ("de", "Attack on Titan"),
+        ])
+    }
+
+    fn language(language: &str, script: ScriptPreference) -> LanguagePreference {
+        LanguagePreference::Language {
+            language: language.to_string(),
+            script,
+        }
+    }
+
+    #[test]
+    fn parses_and_displays_keys() {
+        for (s, language, script, original) in [
+            ("ja", "ja", Script::Native, false),
+            ("ja-ro*", "ja", Script::Romanized, true),
+            ("EN*", "en", Script::Native, true),
+        ] {
+            let key = s.parse::<TitleKey>().unwrap();
+            let expected = TitleKey {
+                language: language.to_string(),
+                script,
+                original,
+            };
+            assert_eq!(key, expected);
+            assert_eq!(key.to_string().parse::<TitleKey>(), Ok(expected));
+        }
+        for s in ["", "*", "-ro", "-ro*"] {
+            assert_eq!(
+                s.parse::<TitleKey>(),
+                Err(format!("Failed to parse title key: {s}"))
+            );
+        }
+    }
+
+    #[test]
+    fn follows_the_fallback_chain() {
+        let titles = shingeki();
+        let best = |preferences: &[LanguagePreference]| titles.best(preferences);
+        assert_eq!(
+            best(&[
+                LanguagePreference::language("fr"),
+                LanguagePreference::language("DE")
+            ]),
+            Some("Angriff auf Titan")
+        );
+        assert_eq!(
+            best(&[
+                language("en", ScriptPreference::Romanized),
+                LanguagePreference::Original(ScriptPreference::RomanizedFirst),
+            ]),
+            Some("Shingeki no Kyojin")
+        );
+        assert_eq!(
+            best(&[
+                LanguagePreference::Original(ScriptPreference::NativeFirst),
+                LanguagePreference::language("en"),
+            ]),
+            Some("進撃の巨人")
+        );
+        assert_eq!(
+            best(&[language("ja", ScriptPreference::Romanized)]),
+            Some("Shingeki no Kyojin")
+        );
+        assert_eq!(
+            best(&[LanguagePreference::Any, LanguagePreference::language("en")]),
+            Some("進撃の巨人")
+        );
+        assert_eq!(
+            best(&[LanguagePreference::language("fr")]),
+            Some("進撃の巨人")
+        );
+        assert_eq!(best(&[]), Some("進撃の巨人"));
+        assert_eq!(TitleSet::default().best(&[LanguagePreference::Any]), None);
+    }
+
+    #[test]
+    fn script_preferences_fall_back_in_order() {
+        let titles = titles(&[("ko", "나 혼자만 레벨업"), ("zh-ro", "Wo Duzi Shengji")]);
+        let pick = |language, script| titles.pick(language, script);
+        assert_eq!(
+            pick("ko", ScriptPreference::RomanizedFirst),
+            Some("나 혼자만 레벨업")
+        );
+        assert_eq!(pick("ko", ScriptPreference::Romanized), None);
+        assert_eq!(
+            pick("zh", ScriptPreference::NativeFirst),
+            Some("Wo Duzi Shengji")
+        );
+        assert_eq!(pick("zh", ScriptPreference::Native), None);
+    }
+
+    #[test]
+    fn original_needs_a_marker() {
+        let titles = titles(&[("ja", "進撃の巨人"), ("en", "Attack on Titan")]);
+        assert_eq!(titles.original_language(), None);
+        assert_eq!(
+            titles.best(&[
+                LanguagePreference::Original(ScriptPreference::NativeFirst),
+                LanguagePreference::language("en"),
+            ]),
+            Some("Attack on Titan")
+        );
+    }
+
+    #[test]
+    fn any_is_independent_of_map_order() {
+        let entries = [
+            ("ja-ro", "Shingeki no Kyojin"),
+            ("JA", "Shingeki"),
+            ("ja", "進撃の巨人"),
+            ("en", "Attack on Titan"),
+            ("ko-ro", "Na Honjaman Level Up"),
+            ("", "invalid"),
+        ];
+        let expected = Some("Attack on Titan");
+        for shift in 0..entries.len() {
+            let mut entries = entries.to_vec();
+            entries.rotate_left(shift);
+            for _ in 0..8 {
+                assert_eq!(titles(&entries).any(), expected);
+                entries.reverse();
+            }
+        }
+        let titles = titles(&[("ja", "進撃の巨人"), ("JA", "Shingeki")]);
+        assert_eq!(titles.any(), Some("Shingeki"));
+    }
+}
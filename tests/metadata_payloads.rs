@@ -0,0 +1,114 @@
+use api_structure::metadata::anilist::{GraphQlResponse, MediaData};
+use api_structure::metadata::kitsu::{Document, MangaResource};
+use api_structure::search::Status;
+use api_structure::title::{LanguagePreference, ScriptPreference};
+
+#[test]
+fn anilist_media_to_metadata() {
+    let response: GraphQlResponse<MediaData> =
+        serde_json::from_str(include_str!("fixtures/anilist_media.json")).unwrap();
+    let metadata = response.into_data().unwrap().media.unwrap().metadata();
+    assert_eq!(metadata.url, "https://anilist.co/manga/30002");
+    assert_eq!(metadata.titles["ja*"], vec!["ベルセルク"]);
+    assert_eq!(metadata.titles["ja-ro*"], vec!["Berserk"]);
+    assert_eq!(metadata.titles["und"], vec!["Berserk: The Prototype"]);
+    assert_eq!(
+        metadata
+            .titles
+            .best(&[LanguagePreference::Original(ScriptPreference::Native)]),
+        Some("ベルセルク")
+    );
+    assert_eq!(metadata.kind.as_deref(), Some("manga"));
+    assert_eq!(
+        metadata.description.as_deref(),
+        Some("His name is Guts, the Black Swordsman.\n\n(Source: Dark Horse)")
+    );
+    let tags = metadata
+        .tags
+        .iter()
+        .map(|v| (v.tag.as_str(), v.spoiler))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        tags,
+        vec![
+            ("Action", false),
+            ("Adventure", false),
+            ("Drama", false),
+            ("Dark Fantasy", false),
+            ("Tragedy", true)
+        ]
+    );
+    assert_eq!(metadata.status, Some(Status::Ongoing));
+    assert_eq!(metadata.authors, vec!["Kentarou Miura"]);
+    assert_eq!(metadata.artists, vec!["Kentarou Miura", "Studio Gaga"]);
+    assert_eq!(
+        metadata.relations,
+        vec![
+            (
+                "adaptation".to_string(),
+                "https://anilist.co/anime/33".to_string()
+            ),
+            (
+                "side_story".to_string(),
+                "https://anilist.co/manga/31002".to_string()
+            )
+        ]
+    );
+    assert_eq!(metadata.covers.len(), 2);
+}
+
+#[test]
+fn anilist_errors_are_returned() {
+    let response: GraphQlResponse<MediaData> = serde_json::from_str(
+        r#"{"data": null, "errors": [{"message": "Not Found.", "status": 404}]}"#,
+    )
+    .unwrap();
+    let err = response.into_data().unwrap_err();
+    assert_eq!(err.cause.as_deref(), Some("Not Found."));
+}
+
+#[test]
+fn kitsu_manga_to_metadata() {
+    let document: Document<MangaResource> =
+        serde_json::from_str(include_str!("fixtures/kitsu_manga.json")).unwrap();
+    let metadata = document.metadata();
+    assert_eq!(metadata.url, "https://kitsu.app/manga/23");
+    assert_eq!(metadata.titles["ja*"], vec!["ベルセルク"]);
+    assert_eq!(metadata.titles["ja-ro*"], vec!["Berserk"]);
+    assert_eq!(metadata.titles["en"], vec!["Berserk"]);
+    assert_eq!(metadata.titles["und"], vec!["Kenpuu Denki Berserk"]);
+    assert_eq!(metadata.kind.as_deref(), Some("manga"));
+    assert_eq!(
+        metadata.description.as_deref(),
+        Some("Guts, a former mercenary now known as the \"Black Swordsman\".\n\n(Source: MU)")
+    );
+    let tags = metadata
+        .tags
+        .iter()
+        .map(|v| (v.tag.as_str(), v.description.as_deref()))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        tags,
+        vec![
+            ("Action", None),
+            ("Dark Fantasy", Some("Fantasy with a dark tone."))
+        ]
+    );
+    assert_eq!(metadata.status, Some(Status::Ongoing));
+    assert_eq!(metadata.authors, vec!["Kentarou Miura"]);
+    assert_eq!(metadata.artists, vec!["Kentarou Miura"]);
+    assert_eq!(
+        metadata.relations,
+        vec![(
+            "adaptation".to_string(),
+            "https://kitsu.app/anime/12".to_string()
+        )]
+    );
+    assert_eq!(
+        metadata.covers,
+        vec![
+            "https://media.kitsu.app/manga/poster_images/23/original.jpg",
+            "https://media.kitsu.app/manga/poster_images/23/large.jpg"
+        ]
+    );
+}
@@ -0,0 +1,194 @@
+use api_structure::metadata::{anilist, kitsu};
+use api_structure::scraper::{
+    ExternalSearchData, SearchValidationError, SimpleSearch, ValidSearch, ValidSearches,
+};
+use std::collections::HashMap;
+
+fn search(text: &str, sort: Option<&str>, tags: &[&str], exclude_tags: &[&str]) -> SimpleSearch {
+    SimpleSearch {
+        search: text.to_string(),
+        sort: sort.map(ToString::to_string),
+        desc: true,
+        status: None,
+        tags: tags.iter().map(ToString::to_string).collect(),
+        exclude_tags: exclude_tags.iter().map(ToString::to_string).collect(),
+        page: 1,
+        filters: HashMap::new(),
+    }
+}
+
+#[test]
+fn anilist_search_matches_fixture() {
+    let mut search = search(
+        " one piece ",
+        Some("score"),
+        &["action", "Slice of Life"],
+        &["Horror"],
+    );
+    search.status = Some("releasing".to_string());
+    search.page = 2;
+    search.filters = HashMap::from([
+        ("year".to_string(), "1997".to_string()),
+        ("format".to_string(), "manga".to_string()),
+    ]);
+    let request = anilist::search_request(&search, 20).unwrap();
+    let expected: serde_json::Value =
+        serde_json::from_str(include_str!("fixtures/anilist_search_request.json")).unwrap();
+    assert_eq!(serde_json::to_value(&request).unwrap(), expected);
+}
+
+#[test]
+fn anilist_text_search_sorts_by_match() {
+    let request = anilist::search_request(&search("berserk", None, &[], &[]), 10).unwrap();
+    assert_eq!(request.variables.sort, vec!["SEARCH_MATCH".to_string()]);
+    assert_eq!(request.variables.page, 1);
+}
+
+#[test]
+fn anilist_rejects_unknown_genre() {
+    let err = anilist::search_request(&search("", None, &["Actoin"], &[]), 10).unwrap_err();
+    assert!(err.message().contains("did you mean \"Action\""));
+}
+
+#[test]
+fn kitsu_search_matches_fixture() {
+    let mut search = search("", Some("popularity"), &["Slice of Life", "Comedy"], &[]);
+    search.page = 3;
+    search.filters = HashMap::from([("subtype".to_string(), "manhwa".to_string())]);
+    let params = kitsu::search_params(&search, 20).unwrap();
+    assert_eq!(
+        params.query_string(),
+        include_str!("fixtures/kitsu_search_query.txt").trim()
+    );
+}
+
+#[test]
+fn kitsu_text_search_omits_sort() {
+    let params = kitsu::search_params(&search("berserk", Some("rating"), &[], &[]), 10).unwrap();
+    assert_eq!(
+        params.query_string(),
+        "filter%5Btext%5D=berserk&page%5Blimit%5D=10&page%5Boffset%5D=0"
+    );
+}
+
+#[test]
+fn kitsu_rejects_excluded_categories() {
+    assert!(kitsu::search_params(&search("", None, &[], &["horror"]), 10).is_err());
+}
+
+#[test]
+fn validation_ignores_case() {
+    let mut search = search("", Some("SCORE"), &["action"], &["HORROR"]);
+    search.status = Some("Releasing".to_string());
+    search.filters = HashMap::from([
+        ("Format".to_string(), "MANGA".to_string()),
+        ("y".to_string(), "1997".to_string()),
+    ]);
+    assert_eq!(search.validate(&ValidSearch::anilist()), Ok(()));
+    let request = anilist::search_request(&search, 10).unwrap();
+    assert_eq!(request.variables.sort, vec!["SCORE_DESC".to_string()]);
+    assert_eq!(request.variables.status.as_deref(), Some("RELEASING"));
+    assert_eq!(request.variables.format.as_deref(), Some("MANGA"));
+    assert_eq!(request.variables.start_date_like.as_deref(), Some("1997%"));
+}
+
+#[test]
+fn validation_reports_every_error() {
+    let mut search = search("", Some("scroe"), &["Action", "Actoin"], &["action"]);
+    search.status = Some("releasnig".to_string());
+    search.filters = HashMap::from([
+        ("yaer".to_string(), "1997".to_string()),
+        ("year".to_string(), "soon".to_string()),
+        ("format".to_string(), "mnaga".to_string()),
+    ]);
+    assert_eq!(
+        search.validate(&ValidSearch::anilist()),
+        Err(vec![
+            SearchValidationError::InvalidSort {
+                value: "scroe".to_string(),
+                suggestion: Some("score".to_string()),
+            },
+            SearchValidationError::InvalidStatus {
+                value: "releasnig".to_string(),
+                suggestion: Some("releasing".to_string()),
+            },
+            SearchValidationError::UnknownTag {
+                value: "Actoin".to_string(),
+                suggestion: Some("Action".to_string()),
+            },
+            SearchValidationError::TagIncludedAndExcluded {
+                tag: "Action".to_string(),
+            },
+            SearchValidationError::InvalidFilter {
+                name: "format".to_string(),
+                value: "mnaga".to_string(),
+                suggestion: Some("manga".to_string()),
+            },
+            SearchValidationError::UnknownFilter {
+                name: "yaer".to_string(),
+                suggestion: Some("year".to_string()),
+            },
+            SearchValidationError::InvalidFilter {
+                name: "year".to_string(),
+                value: "soon".to_string(),
+                suggestion: None,
+            },
+        ])
+    );
+}
+
+#[test]
+fn validation_skips_tags_without_catalog() {
+    let search = search("", None, &["anything"], &[]);
+    assert_eq!(search.validate(&ValidSearch::kitsu()), Ok(()));
+}
+
+fn search_data(query: &str) -> SimpleSearch {
+    match ValidSearches::ValidSearch(ValidSearch::anilist()).search_data(query, 1) {
+        Ok(ExternalSearchData::Simple(search)) => search,
+        Ok(ExternalSearchData::String(_)) => panic!("{query} was not parsed"),
+        Err(err) => panic!("{query}: {}", err.message()),
+    }
+}
+
+#[test]
+fn search_data_collects_free_text() {
+    assert_eq!(search_data("one piece").search, "one piece");
+    let search = search_data(r#"one title:"piece film" releasing: tag:!horror sort:score"#);
+    assert_eq!(search.search, "one piece film");
+    assert_eq!(search.status.as_deref(), Some("releasing"));
+    assert_eq!(search.exclude_tags, vec!["horror".to_string()]);
+    assert_eq!(search.sort.as_deref(), Some("score"));
+
+    let search = search_data("desc: Releasing: horror:! y:1997 f:manga");
+    assert!(search.desc);
+    assert_eq!(search.status.as_deref(), Some("releasing"));
+    assert_eq!(search.exclude_tags, vec!["Horror".to_string()]);
+    assert_eq!(
+        search.filters,
+        HashMap::from([
+            ("year".to_string(), "1997".to_string()),
+            ("format".to_string(), "manga".to_string()),
+        ])
+    );
+    assert!(!search_data("desc:false").desc);
+    let valid = ValidSearches::ValidSearch(ValidSearch::anilist());
+    for query in [
+        "s:",
+        "year:",
+        "t:",
+        "unknown:",
+        "releasing:!",
+        "or:(t:a t:b)",
+    ] {
+        let err = match valid.search_data(query, 1) {
+            Ok(_) => panic!("{query} was accepted"),
+            Err(err) => err,
+        };
+        assert_eq!(
+            err.message(),
+            "Query not supported by the source",
+            "{query}"
+        );
+    }
+}